@@ -3,23 +3,19 @@ const socket = new WebSocket(`ws://${location.host}/socket`);
 //    document.getElementById("iframe").hidden = true;
 //    window.close();
 //});
+
+let currentUrl = null;
 socket.addEventListener('message', event => {
-    document.getElementById("line").innerText = event.data;
-    const state = JSON.parse(event.data);
+    const message = JSON.parse(event.data);
+    if (message.type !== "status") {
+        return;
+    }
+    const state = message.payload;
+    document.getElementById("line").innerText = JSON.stringify(state);
     const frame = document.getElementById("iframe");
-    switch (state.state) {
-        case "Closed":
-            frame.src = "https://op.gg";
-            break;
-        case "Idle":
-            frame.src = `https://op.gg/summoners/${state.info.server}/${state.info.username}`;
-            break;
-        case "ChampSelect":
-            frame.src = `https://op.gg/multisearch/${state.info.server}?summoners=${encodeURIComponent(state.additional_info.join(','))}`;
-            console.log(frame.src)
-            break;
-        case "InGame":
-            frame.src = `https://op.gg/summoners/${state.info.server}/${state.info.username}/ingame`;
-            break;
+    if (currentUrl !== state.url) {
+        currentUrl = state.url;
+        frame.src = state.url;
+        console.log(frame.src)
     }
-});
\ No newline at end of file
+});
@@ -0,0 +1,84 @@
+//! Runs the [mock client](mock_lcu) to exercise the helper without a real client.
+//!
+//! The state of the mock is controlled by commands read line by line from stdin:
+//!
+//! ```text
+//! set <uri> <json>     replace the resource at <uri> and push an Update event
+//! create <uri> <json>  create the resource at <uri> and push a Create event
+//! delete <uri>         remove the resource at <uri> and push a Delete event
+//! phase <phase>        shorthand for `set /lol-gameflow/v1/gameflow-phase "<phase>"`
+//! readycheck           start a ready check that can be accepted through the api
+//! decline              decline the ready check like the player would
+//! requests             print every request received so far
+//! quit                 remove the lockfile and exit
+//! ```
+//!
+//! Point `certificate_path` in the helper's `config.json` to `tests/mock_lcu/cert.pem` and `client_path`
+//! to the directory passed to the mock:
+//!
+//! ```text
+//! cargo run --example mock_lcu -- <client dir> [port]
+//! ```
+
+#[path = "../tests/mock_lcu/mod.rs"]
+mod mock_lcu;
+
+use std::path::PathBuf;
+use anyhow::{anyhow, Context, Result};
+use log::LevelFilter;
+use serde_json::Value;
+use crate::mock_lcu::MockLcu;
+
+async fn handle_command(mock: &MockLcu, line: &str) -> Result<bool> {
+    let line = line.trim();
+    let (command, args) = line.split_once(' ').unwrap_or((line, ""));
+    match command {
+        "" => {},
+        "set" | "create" => {
+            let (uri, json) = args.split_once(' ').context("expected <uri> <json>")?;
+            let value: Value = serde_json::from_str(json)?;
+            match command {
+                "set" => mock.set(uri, value).await,
+                _ => mock.create(uri, value).await
+            }
+        },
+        "delete" => mock.delete(args).await,
+        "phase" => mock.set_phase(args).await,
+        "readycheck" => mock.start_ready_check().await,
+        "decline" => mock.respond_to_ready_check("Declined").await,
+        "requests" => for request in mock.requests().await {
+            println!("{}", request);
+        },
+        "quit" => return Ok(false),
+        _ => log::warn!("Unknown command: {}", command)
+    }
+    Ok(true)
+}
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    env_logger::builder()
+        .filter_level(LevelFilter::Info)
+        .format_timestamp(None)
+        .parse_default_env()
+        .init();
+
+    let mut args = std::env::args().skip(1);
+    let client_dir = PathBuf::from(args.next().ok_or_else(|| anyhow!("usage: mock_lcu <client dir> [port]"))?);
+    let port: u16 = args.next().map(|p| p.parse()).transpose()?.unwrap_or(0);
+
+    let mock = MockLcu::start(&client_dir, port).await?;
+
+    let stdin = async_std::io::stdin();
+    let mut line = String::new();
+    while stdin.read_line(&mut line).await? > 0 {
+        match handle_command(&mock, &line).await {
+            Ok(true) => {},
+            Ok(false) => break,
+            Err(err) => log::warn!("Invalid command: {}", err)
+        }
+        line.clear();
+    }
+
+    mock.stop().await
+}
@@ -0,0 +1,99 @@
+use serde::Serialize;
+use serde_json::json;
+use tide::{Body, Request, Response, Server, StatusCode};
+use futures::StreamExt;
+use tide::http::headers::HeaderValue;
+use tide::security::{CorsMiddleware, Origin};
+use tide::sse::Sender;
+use tiny_champ_select_helper::client_state::ClientStatus;
+use crate::AppState;
+use crate::protocol::{self, ServerMessage, StatusMessage};
+
+/// The read-only JSON endpoints mounted at `/api`, meant to be polled by scripts and overlays.
+///
+/// Overlays are served from other origins, so these `GET` endpoints and the `/api/events` stream, and only these,
+/// allow cross-origin reads.
+pub fn api(state: AppState) -> Server<AppState> {
+    let mut api = tide::with_state(state);
+    api.with(CorsMiddleware::new()
+        .allow_methods("GET, OPTIONS".parse::<HeaderValue>().unwrap())
+        .allow_origin(Origin::Any)
+        .allow_credentials(false));
+    api.at("/status").get(status);
+    api.at("/champ-select").get(champ_select);
+    api.at("/summoner").get(summoner);
+    api.at("/url").get(url);
+    api.at("/schema").get(schema);
+    api.at("/events").get(tide::sse::endpoint(events));
+    api
+}
+
+fn json_or_not_found<T: Serialize>(value: Option<T>) -> tide::Result<Response> {
+    Ok(match value {
+        Some(value) => Response::builder(StatusCode::Ok)
+            .body(Body::from_json(&value)?)
+            .build(),
+        None => Response::new(StatusCode::NotFound)
+    })
+}
+
+async fn status(req: Request<AppState>) -> tide::Result<Body> {
+    Body::from_json(&req.state().status.latest().await)
+}
+
+/// The current champ select session along with the players of the own team. `404` outside of champ select.
+async fn champ_select(req: Request<AppState>) -> tide::Result<Response> {
+    let status = req.state().status.latest().await;
+    json_or_not_found(status.champ_select.map(|session| json!({
+        "session": session,
+        "players": status.players
+    })))
+}
+
+/// The logged in summoner. `404` while the client is closed.
+async fn summoner(req: Request<AppState>) -> tide::Result<Response> {
+    json_or_not_found(req.state().status.latest().await.info)
+}
+
+async fn url(req: Request<AppState>) -> tide::Result<Body> {
+    let provider = req.state().provider().await;
+    Body::from_json(&json!({
+        "provider": &provider,
+        "name": provider.name(),
+        "url": req.state().url().await
+    }))
+}
+
+/// The JSON schemas of the messages of `/socket` and `/api/events` and of the commands accepted by `/socket`.
+async fn schema(_: Request<AppState>) -> tide::Result<Body> {
+    Body::from_json(&json!({
+        "messages": protocol::message_schema(),
+        "commands": protocol::command_schema()
+    }))
+}
+
+/// Streams every status change as a `status` message. Each event carries `<epoch>-<number>` of the status as its id,
+/// so a client reconnecting with an up-to-date `Last-Event-ID` doesn't get the latest state again,
+/// while one that connected to an earlier run of the helper does.
+async fn events(req: Request<AppState>, sender: Sender) -> tide::Result<()> {
+    let epoch = req.state().epoch;
+    let last_event_id = req
+        .header("Last-Event-ID")
+        .and_then(|id| id.as_str().split_once('-'))
+        .and_then(|(e, n)| Some((e.parse::<u128>().ok()?, n.parse::<u64>().ok()?)));
+    let (id, status, mut receiver) = req.state().status.subscribe_numbered().await;
+    if last_event_id != Some((epoch, id)) {
+        send_status(&req, &sender, id, &status).await?;
+    }
+    while let Some((id, status)) = receiver.next().await {
+        send_status(&req, &sender, id, &status).await?;
+    }
+    Ok(())
+}
+
+async fn send_status(req: &Request<AppState>, sender: &Sender, id: u64, status: &ClientStatus) -> tide::Result<()> {
+    let provider = req.state().provider().await;
+    let message = ServerMessage::Status(StatusMessage::new(status, &provider)).to_json()?;
+    let id = format!("{}-{}", req.state().epoch, id);
+    Ok(sender.send("status", message, Some(&id)).await?)
+}
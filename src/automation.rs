@@ -0,0 +1,266 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use anyhow::{ensure, Result};
+use async_std::task;
+use error_tools::WrapError;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use surf::Client;
+use crate::champ_select::ChampSelectSession;
+use crate::client_state::ClientStatus;
+use crate::connection::SharedClient;
+use crate::lcu::{endpoints, LcuEventKind};
+use crate::relay::EventRelay;
+use crate::util::ReceiveWrapper;
+
+/// The matchmaking ready check as reported by `/lol-matchmaking/v1/ready-check`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ReadyCheck {
+    /// `Invalid`, `InProgress`, `EveryoneReady`, `StrangerNotReady` or `PartyNotReady`.
+    pub state: String,
+    /// `None`, `Accepted` or `Declined`.
+    pub player_response: String,
+    pub timer: f64
+}
+
+impl ReadyCheck {
+
+    pub async fn load_from(client: &Client) -> Result<Self> {
+        Ok(client
+            .get(endpoints::READY_CHECK)
+            .recv_json::<Self>()
+            .await.wrapped()?)
+    }
+
+    /// Checks if the ready check is running and the player hasn't responded yet.
+    pub fn is_pending(&self) -> bool {
+        self.state == "InProgress" && self.player_response == "None"
+    }
+
+}
+
+/// Accepts ready checks after a fixed delay, giving the player a chance to decline manually.
+///
+/// Clones share the enabled flag, so the automation can be toggled while it is running.
+#[derive(Debug, Clone)]
+pub struct AutoAccept {
+    enabled: Arc<AtomicBool>,
+    delay: Duration
+}
+
+impl AutoAccept {
+
+    pub fn new(enabled: bool, delay: Duration) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+            delay
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Flips the enabled flag and returns the new value.
+    pub fn toggle(&self) -> bool {
+        !self.enabled.fetch_xor(true, Ordering::Relaxed)
+    }
+
+    /// Watches the ready check through `relay` until the connection manager is gone.
+    pub async fn run(self, relay: EventRelay, client: SharedClient) {
+        relay.subscribe(endpoints::READY_CHECK).await;
+        let mut events = relay.events();
+        // The client sends an update every second while the ready check is running,
+        // so remember whether the current one was already handled.
+        let mut handled = false;
+        while let Some(event) = events.next().await {
+            if event.uri != endpoints::READY_CHECK {
+                continue
+            }
+            let ready_check = match event.kind {
+                LcuEventKind::Delete => ReadyCheck::default(),
+                _ => match serde_json::from_value::<ReadyCheck>(event.data) {
+                    Ok(ready_check) => ready_check,
+                    Err(err) => {
+                        log::warn!("Invalid ready check: {}", err);
+                        continue
+                    }
+                }
+            };
+            if !ready_check.is_pending() {
+                handled = false;
+            } else if !handled && self.is_enabled() {
+                handled = true;
+                if let Err(err) = self.accept(&client).await {
+                    log::warn!("Could not accept the ready check: {}", err);
+                }
+            }
+        }
+    }
+
+    async fn accept(&self, client: &SharedClient) -> Result<()> {
+        task::sleep(self.delay).await;
+        let client = match client.read().await.clone() {
+            Some(client) => client,
+            None => return Ok(())
+        };
+        // The player might have responded or been toggled off in the meantime.
+        if ReadyCheck::load_from(&client).await?.is_pending() && self.is_enabled() {
+            log::info!("Accepting ready check");
+            client
+                .post(endpoints::READY_CHECK_ACCEPT)
+                .await.wrapped()?;
+        }
+        Ok(())
+    }
+
+}
+
+/// Champion ids in order of preference, keyed by the assigned position (`top`, `jungle`, `middle`,
+/// `bottom` or `utility`). The `default` list is used in blind pick and for positions without a list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChampionPriorities {
+    pub picks: HashMap<String, Vec<i64>>,
+    pub bans: HashMap<String, Vec<i64>>
+}
+
+fn for_position<'a>(lists: &'a HashMap<String, Vec<i64>>, position: &str) -> &'a [i64] {
+    lists
+        .get(position)
+        .or_else(|| lists.get("default"))
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+async fn update_action(client: &Client, action_id: i64, champion: i64, completed: bool) -> Result<()> {
+    let response = client
+        .patch(format!("{}/{}", endpoints::CHAMP_SELECT_ACTIONS, action_id))
+        .body_json(&json!({
+            "championId": champion,
+            "completed": completed
+        })).wrapped()?
+        .await.wrapped()?;
+    ensure!(response.status().is_success(), "updating action {} failed with {}", action_id, response.status());
+    Ok(())
+}
+
+/// Hovers, locks and bans champions for the local player based on [`ChampionPriorities`].
+///
+/// Picks are hovered as soon as the pick turn of the player is known, bans once the ban turn started,
+/// unless the player already hovered a champion. Once the turn is running, the hovered champion, or the best
+/// available one if it was banned or picked in the meantime, is hovered for `lock_delay` and then locked in.
+/// If the player hovers something else or the turn ends during that delay, the action is left to the player.
+/// In hover only mode nothing is ever locked in, the player has to confirm every choice manually.
+#[derive(Debug, Clone)]
+pub struct AutoPickBan {
+    priorities: ChampionPriorities,
+    hover_only: bool,
+    lock_delay: Duration
+}
+
+impl AutoPickBan {
+
+    pub fn new(priorities: ChampionPriorities, hover_only: bool, lock_delay: Duration) -> Self {
+        Self {
+            priorities,
+            hover_only,
+            lock_delay
+        }
+    }
+
+    /// Acts on every champ select session published through `status` until the status channel closes.
+    pub async fn run(self, status: ReceiveWrapper<ClientStatus>, client: SharedClient) {
+        let (_, mut statuses) = status.subscribe().await;
+        // The actions that were already hovered (`false`) or locked (`true`) in the current champ select.
+        let mut done = HashSet::new();
+        while let Some(status) = statuses.next().await {
+            let session = match status.champ_select {
+                Some(session) => session,
+                None => {
+                    done.clear();
+                    continue
+                }
+            };
+            let client = match client.read().await.clone() {
+                Some(client) => client,
+                None => continue
+            };
+            if let Err(err) = self.act(&client, &session, &mut done).await {
+                log::warn!("Champ select automation failed: {}", err);
+            }
+        }
+    }
+
+    async fn act(&self, client: &Client, session: &ChampSelectSession, done: &mut HashSet<(i64, bool)>) -> Result<()> {
+        let position = session
+            .local_player()
+            .map_or("", |player| player.assigned_position.as_str());
+        for action in session.local_actions().filter(|action| !action.completed) {
+            let (priorities, candidates) = match action.kind.as_str() {
+                "pick" => (&self.priorities.picks, endpoints::PICKABLE_CHAMPIONS),
+                "ban" if action.is_in_progress => (&self.priorities.bans, endpoints::BANNABLE_CHAMPIONS),
+                _ => continue
+            };
+            let lock = action.is_in_progress && !self.hover_only;
+            // Don't replace a hover of the player, and don't repeat what was already done.
+            if (!lock && action.champion_id > 0) || done.contains(&(action.id, lock)) {
+                continue
+            }
+
+            let candidates: HashSet<i64> = client
+                .get(candidates)
+                .recv_json::<Vec<i64>>()
+                .await.wrapped()?
+                .into_iter()
+                .collect();
+            let unavailable = session.unavailable_champions();
+            let is_available = |id: &i64| candidates.contains(id) && !unavailable.contains(id);
+            let champion = Some(action.champion_id)
+                .filter(is_available)
+                .or_else(|| for_position(priorities, position).iter().copied().find(is_available));
+
+            match champion {
+                Some(champion) => {
+                    if lock && !self.confirm_lock(client, action.id, action.champion_id, champion).await? {
+                        done.insert((action.id, true));
+                        continue
+                    }
+                    log::info!("{} champion {} for action {}", if lock { "Locking" } else { "Hovering" }, champion, action.id);
+                    update_action(client, action.id, champion, lock).await?;
+                    done.insert((action.id, lock));
+                },
+                None => log::debug!("No champion available for {} as {}", action.kind, position)
+            }
+        }
+        Ok(())
+    }
+
+    /// Hovers `champion` for the lock delay and checks that the turn is still running and the player
+    /// didn't hover something else in the meantime.
+    async fn confirm_lock(&self, client: &Client, action_id: i64, hovered: i64, champion: i64) -> Result<bool> {
+        if hovered != champion {
+            log::info!("Hovering champion {} for action {}", champion, action_id);
+            update_action(client, action_id, champion, false).await?;
+        }
+        task::sleep(self.lock_delay).await;
+        let session = ChampSelectSession::load_from(client).await?;
+        let unchanged = session
+            .local_actions()
+            .find(|action| action.id == action_id)
+            .map_or(false, |action| action.is_in_progress && !action.completed && action.champion_id == champion);
+        if !unchanged {
+            log::info!("Not locking champion {} for action {} as the action changed", champion, action_id);
+        }
+        Ok(unchanged)
+    }
+
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use surf::Client;
+use anyhow::Result;
+use error_tools::WrapError;
+use crate::lcu::endpoints;
+
+/// The state of the current champ select as reported by `/lol-champ-select/v1/session`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all(deserialize = "camelCase"))]
+#[schemars(rename_all = "snake_case")]
+pub struct ChampSelectSession {
+    pub local_player_cell_id: i64,
+    pub my_team: Vec<TeamMember>,
+    pub their_team: Vec<TeamMember>,
+    /// The pick and ban turns, grouped by the phase in which they happen.
+    pub actions: Vec<Vec<SessionAction>>,
+    pub bans: Bans,
+    pub timer: Timer
+}
+
+/// A single cell of either team.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all(deserialize = "camelCase"))]
+#[schemars(rename_all = "snake_case")]
+pub struct TeamMember {
+    pub cell_id: i64,
+    /// The locked or hovered champion, `0` if there is none.
+    pub champion_id: i64,
+    /// The champion the player declared before their turn, `0` if there is none.
+    pub champion_pick_intent: i64,
+    /// The position in draft modes, empty otherwise.
+    pub assigned_position: String,
+    pub spell1_id: u64,
+    pub spell2_id: u64,
+    /// `0` for bots and for players of the enemy team.
+    pub summoner_id: u64,
+    /// Empty for bots and for players of the enemy team.
+    pub puuid: String,
+    pub game_name: String,
+    pub tag_line: String,
+    pub team: i64
+}
+
+/// A pick or ban turn of a single cell.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all(deserialize = "camelCase"))]
+#[schemars(rename_all = "snake_case")]
+pub struct SessionAction {
+    pub id: i64,
+    pub actor_cell_id: i64,
+    pub champion_id: i64,
+    pub completed: bool,
+    pub is_ally_action: bool,
+    pub is_in_progress: bool,
+    /// `pick`, `ban` or `ten_bans_reveal`.
+    #[serde(rename(deserialize = "type"))]
+    #[schemars(rename = "kind")]
+    pub kind: String
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all(deserialize = "camelCase"))]
+#[schemars(rename_all = "snake_case")]
+pub struct Bans {
+    pub my_team_bans: Vec<i64>,
+    pub their_team_bans: Vec<i64>,
+    pub num_bans: i64
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all(deserialize = "camelCase"))]
+#[schemars(rename_all = "snake_case")]
+pub struct Timer {
+    /// `PLANNING`, `BAN_PICK`, `FINALIZATION` or `GAME_STARTING`.
+    pub phase: String,
+    pub adjusted_time_left_in_phase: i64,
+    pub total_time_in_phase: i64,
+    pub is_infinite: bool
+}
+
+impl ChampSelectSession {
+
+    /// Loads the session of the current champ select.
+    pub async fn load_from(client: &Client) -> Result<Self> {
+        Ok(client
+            .get(endpoints::CHAMP_SELECT_SESSION)
+            .recv_json::<Self>()
+            .await.wrapped()?)
+    }
+
+    /// The cell of the local player.
+    pub fn local_player(&self) -> Option<&TeamMember> {
+        self.my_team
+            .iter()
+            .find(|member| member.cell_id == self.local_player_cell_id)
+    }
+
+    /// The pick and ban turns of the local player, in the order they happen.
+    pub fn local_actions(&self) -> impl Iterator<Item = &SessionAction> {
+        self.actions
+            .iter()
+            .flatten()
+            .filter(move |action| action.actor_cell_id == self.local_player_cell_id)
+    }
+
+    /// The champion the local player locked in, if any.
+    pub fn locked_champion(&self) -> Option<i64> {
+        self.local_actions()
+            .filter(|action| action.kind == "pick" && action.completed && action.champion_id > 0)
+            .map(|action| action.champion_id)
+            .last()
+    }
+
+    /// The champions nobody can pick or ban anymore: bans, completed picks and the picks
+    /// and declared intents of teammates.
+    pub fn unavailable_champions(&self) -> HashSet<i64> {
+        let bans = self.bans.my_team_bans
+            .iter()
+            .chain(&self.bans.their_team_bans)
+            .copied();
+        let completed = self.actions
+            .iter()
+            .flatten()
+            .filter(|action| action.completed)
+            .map(|action| action.champion_id);
+        let teammates = self.my_team
+            .iter()
+            .filter(|member| member.cell_id != self.local_player_cell_id)
+            .flat_map(|member| [member.champion_id, member.champion_pick_intent]);
+        bans.chain(completed)
+            .chain(teammates)
+            .filter(|id| *id > 0)
+            .collect()
+    }
+
+    /// The summoner ids of all human players in the own team.
+    pub fn my_team_summoner_ids(&self) -> Vec<u64> {
+        self.my_team
+            .iter()
+            .map(|member| member.summoner_id)
+            .filter(|id| *id > 0)
+            .collect()
+    }
+
+}
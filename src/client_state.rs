@@ -1,61 +1,208 @@
 use serde_json::Value;
 use surf::Client;
-use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize, Serializer};
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::Schema;
 use anyhow::Result;
 use error_tools::{OptionToError, WrapError};
+use crate::champ_select::ChampSelectSession;
+use crate::connection::ConnectionState;
+use crate::history::PlayerHistory;
+use crate::lcu::endpoints;
+use crate::region::Region;
 
-#[derive(Debug, Clone, Serialize)]
+/// A player as returned by the summoner endpoints.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, rename_all(deserialize = "camelCase"))]
+#[schemars(rename_all = "snake_case")]
+pub struct Summoner {
+    pub summoner_id: u64,
+    pub puuid: String,
+    /// The legacy summoner name. Empty for accounts that only have a Riot ID.
+    pub display_name: String,
+    pub game_name: String,
+    pub tag_line: String
+}
+
+impl Summoner {
+    /// The Riot ID (`gameName#tagLine`), or the display name for accounts without one.
+    pub fn riot_id(&self) -> String {
+        match self.game_name.is_empty() {
+            true => self.display_name.clone(),
+            false => format!("{}#{}", self.game_name, self.tag_line)
+        }
+    }
+}
+
+/// The region and name of the logged in summoner.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct BasicInfo {
+    /// The lowercase name of the region, e.g. `euw`.
     pub server: String,
-    pub username: String
+    /// `None` if the client reports a region that isn't known yet.
+    pub region: Option<Region>,
+    /// The Riot ID of the summoner, see [`Summoner::riot_id`].
+    pub username: String,
+    pub game_name: String,
+    pub tag_line: String,
+    pub puuid: String
 }
 
 impl BasicInfo {
+    /// Loads the region and name of the current summoner.
     pub async fn load_from(client: &Client) -> Result<Self> {
+        let summoner = client
+            .get(endpoints::CURRENT_SUMMONER)
+            .recv_json::<Summoner>()
+            .await.wrapped()?;
+        let region_name = client
+            .get(endpoints::REGION_LOCALE)
+            .recv_json::<Value>()
+            .await.wrapped()?
+            .get("region").err()?
+            .as_str().err()?
+            .to_string();
+        let region = match region_name.parse::<Region>() {
+            Ok(region) => Some(region),
+            Err(err) => {
+                log::warn!("{}", err);
+                None
+            }
+        };
         Ok(Self {
-            server: client
-                .get("/riotclient/region-locale")
-                .recv_json::<Value>()
-                .await.wrapped()?
-                .get("region").err()?
-                .as_str().err()?
-                .to_lowercase(),
-            username: client
-                .get("/lol-summoner/v1/current-summoner")
-                .recv_json::<Value>()
-                .await.wrapped()?
-                .get("displayName").err()?
-                .as_str().err()?
-                .into()
+            server: region.map_or_else(|| region_name.to_lowercase(), |region| region.slug()),
+            region,
+            username: summoner.riot_id(),
+            game_name: summoner.game_name,
+            tag_line: summoner.tag_line,
+            puuid: summoner.puuid
         })
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
-pub enum ClientState {
-    Closed,
-    Idle,
+/// A gameflow phase as reported by `/lol-gameflow/v1/gameflow-phase`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum GameflowPhase {
+    None,
+    Lobby,
+    Matchmaking,
+    CheckedIntoTournament,
+    ReadyCheck,
     ChampSelect,
-    InGame
+    GameStart,
+    FailedToLaunch,
+    InProgress,
+    Reconnect,
+    WaitingForStats,
+    PreEndOfGame,
+    EndOfGame,
+    TerminatedInError,
+    /// A phase that was added to the client after this enum was written.
+    Unknown(String)
+}
+
+impl GameflowPhase {
+
+    /// Loads the current gameflow phase.
+    pub async fn load_from(client: &Client) -> Result<Self> {
+        Ok(Self::from(client
+            .get(endpoints::GAMEFLOW_PHASE)
+            .recv_json::<Value>()
+            .await.wrapped()?
+            .as_str().err()?))
+    }
+
+    /// The name the client uses for this phase.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::None => "None",
+            Self::Lobby => "Lobby",
+            Self::Matchmaking => "Matchmaking",
+            Self::CheckedIntoTournament => "CheckedIntoTournament",
+            Self::ReadyCheck => "ReadyCheck",
+            Self::ChampSelect => "ChampSelect",
+            Self::GameStart => "GameStart",
+            Self::FailedToLaunch => "FailedToLaunch",
+            Self::InProgress => "InProgress",
+            Self::Reconnect => "Reconnect",
+            Self::WaitingForStats => "WaitingForStats",
+            Self::PreEndOfGame => "PreEndOfGame",
+            Self::EndOfGame => "EndOfGame",
+            Self::TerminatedInError => "TerminatedInError",
+            Self::Unknown(phase) => phase
+        }
+    }
+
 }
 
-impl From<&str> for ClientState {
+impl From<&str> for GameflowPhase {
     fn from(s: &str) -> Self {
         match s {
+            "None" => Self::None,
+            "Lobby" => Self::Lobby,
+            "Matchmaking" => Self::Matchmaking,
+            "CheckedIntoTournament" => Self::CheckedIntoTournament,
+            "ReadyCheck" => Self::ReadyCheck,
             "ChampSelect" => Self::ChampSelect,
-            "InProgress" => Self::InGame,
-            _ => Self::Idle
+            "GameStart" => Self::GameStart,
+            "FailedToLaunch" => Self::FailedToLaunch,
+            "InProgress" => Self::InProgress,
+            "Reconnect" => Self::Reconnect,
+            "WaitingForStats" => Self::WaitingForStats,
+            "PreEndOfGame" => Self::PreEndOfGame,
+            "EndOfGame" => Self::EndOfGame,
+            "TerminatedInError" => Self::TerminatedInError,
+            other => Self::Unknown(other.to_string())
         }
     }
 }
 
-impl ClientState {
-    pub async fn load_from(client: &Client) -> Result<Self> {
-        Ok(Self::from(client
-            .get("/lol-gameflow/v1/gameflow-phase")
-            .recv_json::<Value>()
-            .await.wrapped()?
-            .as_str().err()?))
+impl Display for GameflowPhase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for GameflowPhase {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl JsonSchema for GameflowPhase {
+    fn schema_name() -> String {
+        "GameflowPhase".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl Default for GameflowPhase {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// A coarse summary of the gameflow phase of the client.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, JsonSchema)]
+pub enum ClientState {
+    Closed,
+    Idle,
+    ChampSelect,
+    InGame
+}
+
+impl From<&GameflowPhase> for ClientState {
+    fn from(phase: &GameflowPhase) -> Self {
+        match phase {
+            GameflowPhase::ChampSelect => Self::ChampSelect,
+            GameflowPhase::InProgress => Self::InGame,
+            _ => Self::Idle
+        }
     }
 }
 
@@ -65,61 +212,111 @@ impl Default for ClientState {
     }
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+/// Everything the helper knows about the client at a given point in time.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
 pub struct ClientStatus {
+    pub connection: ConnectionState,
+    pub phase: GameflowPhase,
     pub state: ClientState,
     pub info: Option<BasicInfo>,
-    pub additional_info: Option<Vec<String>>
+    pub champ_select: Option<ChampSelectSession>,
+    /// The players of the own team during champ select.
+    pub players: Option<Vec<Summoner>>,
+    /// The recent games of the players of the own team during champ select.
+    pub history: Option<Vec<PlayerHistory>>
 }
 
 impl ClientStatus {
 
-    pub async fn update(&mut self, client: &Client, state: ClientState) -> Result<()> {
-        match state {
-            ClientState::Closed => self.info = None,
-            _ => if self.info.is_none() {
-                self.info = Some(BasicInfo::load_from(client).await?)
-            }
+    /// Transitions to `phase`, loading any information that becomes available in the new phase.
+    pub async fn update(&mut self, client: &Client, phase: GameflowPhase) -> Result<()> {
+        let state = ClientState::from(&phase);
+        if self.info.is_none() {
+            self.info = Some(BasicInfo::load_from(client).await?)
         }
         match state {
-            ClientState::ChampSelect => if self.additional_info.is_none() {
-                let player_ids = client
-                    .get("/lol-champ-select/v1/session")
-                    .recv_json::<Value>()
-                    .await.wrapped()?
-                    .get("myTeam").err()?
-                    .as_array().err()?
-                    .iter()
-                    .filter_map(|je| je
-                        .get("summonerId")
-                        .and_then(|id| id.as_u64()))
-                    .filter(|id| *id > 0)
-                    .collect::<Vec<_>>();
-
-                let player_names = client
-                    .get(format!("/lol-summoner/v2/summoner-names?ids={:?}", player_ids))
-                    .recv_json::<Value>()
-                    .await.wrapped()?
-                    .as_array().err()?
-                    .iter()
-                    .filter_map(|je| je
-                        .get("displayName")
-                        .and_then(|name| name.as_str())
-                        .map(|name| name.to_string()))
-                    .collect::<Vec<_>>();
-
-                self.additional_info = Some(player_names)
+            ClientState::ChampSelect => if self.champ_select.is_none() {
+                let session = ChampSelectSession::load_from(client).await?;
+                self.update_champ_select(client, Some(session)).await?;
             }
-            _ => self.additional_info = None
+            _ => self.update_champ_select(client, None).await?
         }
+        self.phase = phase;
         self.state = state;
         Ok(())
     }
 
+    /// Replaces the champ select session, reloading the players of the team if it changed.
+    ///
+    /// Loading the match histories of a new team takes a while, so that is left to the caller,
+    /// see [`missing_history`](Self::missing_history).
+    pub async fn update_champ_select(&mut self, client: &Client, session: Option<ChampSelectSession>) -> Result<()> {
+        match session {
+            Some(session) => {
+                let player_ids = session.my_team_summoner_ids();
+                let previous_ids = self.champ_select
+                    .as_ref()
+                    .map(ChampSelectSession::my_team_summoner_ids);
+                if self.players.is_none() || previous_ids.as_ref() != Some(&player_ids) {
+                    self.players = Some(load_summoners(client, &player_ids).await?);
+                    self.history = None;
+                }
+                if let Some(history) = &mut self.history {
+                    PlayerHistory::update_champions(history, &session);
+                }
+                self.champ_select = Some(session);
+            }
+            None => {
+                self.champ_select = None;
+                self.players = None;
+                self.history = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// The players of the own team whose match histories haven't been loaded yet.
+    pub fn missing_history(&self) -> Option<&[Summoner]> {
+        match self.history {
+            Some(_) => None,
+            None => self.players.as_deref()
+        }
+    }
+
+    /// Sets the match histories loaded for `players`, unless the team changed in the meantime.
+    pub fn set_history(&mut self, players: &[Summoner], mut history: Vec<PlayerHistory>) -> bool {
+        if self.missing_history() != Some(players) {
+            return false
+        }
+        if let Some(session) = &self.champ_select {
+            PlayerHistory::update_champions(&mut history, session);
+        }
+        self.history = Some(history);
+        true
+    }
+
+    /// Forgets everything about the client after it shut down.
+    pub fn close(&mut self) {
+        self.phase = GameflowPhase::None;
+        self.state = ClientState::Closed;
+        self.info = None;
+        self.champ_select = None;
+        self.players = None;
+        self.history = None;
+    }
+
+    /// Loads the current status from scratch.
     pub async fn load_from(client: &Client) -> Result<Self> {
         let mut result = Self::default();
-        result.update(client, ClientState::load_from(client).await?).await?;
+        result.update(client, GameflowPhase::load_from(client).await?).await?;
         Ok(result)
     }
 
-}
\ No newline at end of file
+}
+
+async fn load_summoners(client: &Client, player_ids: &[u64]) -> Result<Vec<Summoner>> {
+    Ok(client
+        .get(format!("/lol-summoner/v2/summoners?ids={:?}", player_ids))
+        .recv_json::<Vec<Summoner>>()
+        .await.wrapped()?)
+}
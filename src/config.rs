@@ -1,62 +1,177 @@
-use std::fs;
-use std::path::Path;
+use std::{fs, io};
+use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use anyhow::{anyhow, ensure, Result};
 use directories::ProjectDirs;
 use error_tools::OptionToError;
 use native_dialog::{FileDialog, MessageDialog, MessageType};
+use tiny_champ_select_helper::automation::ChampionPriorities;
+use tiny_champ_select_helper::providers::StatsProvider;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
-    pub client_path: String,
+    pub client_path: Option<String>,
     pub server_url: String,
+    #[serde(default)]
+    pub certificate_path: Option<String>,
+    #[serde(default)]
+    pub provider: StatsProvider,
+    /// Enables the `/lcu/*` proxy if present.
+    #[serde(default)]
+    pub lcu_proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub auto_accept: AutoAcceptConfig,
+    #[serde(default)]
+    pub auto_pick_ban: AutoPickBanConfig,
+    /// Applies the rune page, summoner spells and item sets from `presets.json` when a champion is locked in.
+    #[serde(default)]
+    pub import_presets: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoAcceptConfig {
+    pub enabled: bool,
+    /// How long to wait before accepting, so there is time to decline manually.
+    pub delay_ms: u64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AutoPickBanConfig {
+    pub enabled: bool,
+    /// Only hover picks and bans, never lock them in.
+    pub hover_only: bool,
+    /// How long a choice stays hovered before it is locked, so there is time to change it manually.
+    pub lock_delay_ms: u64,
+    pub priorities: ChampionPriorities
+}
+
+impl Default for AutoAcceptConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            delay_ms: 2000
+        }
+    }
+}
+
+impl Default for AutoPickBanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hover_only: false,
+            lock_delay_ms: 3000,
+            priorities: ChampionPriorities::default()
+        }
+    }
+}
+
+/// The requests the `/lcu/*` proxy forwards to the client. Everything else is rejected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyConfig {
+    pub allow: Vec<ProxyRule>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyRule {
+    /// An LCU path like `/lol-summoner/v1/current-summoner`. A trailing `*` matches every path with that prefix.
+    pub path: String,
+    /// The allowed http methods. Only `GET` if empty.
+    #[serde(default)]
+    pub methods: Vec<String>
+}
+
+impl ProxyRule {
+    pub fn matches(&self, method: &str, path: &str) -> bool {
+        let path_matches = match self.path.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => path == self.path
+        };
+        let method_matches = match self.methods.is_empty() {
+            true => method.eq_ignore_ascii_case("GET"),
+            false => self.methods.iter().any(|m| m.eq_ignore_ascii_case(method))
+        };
+        path_matches && method_matches
+    }
+}
+
+#[cfg(unix)]
+const SELECT_CLIENT_TEXT: &str = "Please select the install directory of the League Client\nFor Example: C:/Riot Games/League of Legends\n\nCancel to detect the running client automatically instead";
+#[cfg(not(unix))]
+const SELECT_CLIENT_TEXT: &str = "Please select the install directory of the League Client\nFor Example: C:/Riot Games/League of Legends";
+
 impl Config {
 
     pub fn initialize() -> Result<Self> {
-        let dirs = ProjectDirs::from("com.github", "sidit77", "tiny-champ-select-helper").err()?;
-        let config_path = dirs.config_dir().join("config.json");
+        let config_path = config_path()?;
         let config = match Self::load(&config_path) {
             Ok(config) => {
                 log::info!("Config found");
                 config
             }
-            Err(err) => {
-                log::info!("Config loading error: {}", err);
+            Err(err) if is_not_found(&err) => {
+                log::info!("No config found. Creating a new one");
                 let config = Self::new()?;
                 config.save(&config_path)?;
                 config
             }
+            // Never overwrite an existing config, it might contain hand-written templates or priorities.
+            Err(err) => {
+                MessageDialog::new()
+                    .set_text(&format!("Invalid config in {}:\n{}", config_path.display(), err))
+                    .set_type(MessageType::Error)
+                    .show_alert()?;
+                return Err(err)
+            }
         };
         Ok(config)
     }
 
     fn new() -> Result<Self> {
         MessageDialog::new()
-            .set_text("Please select the install directory of the League Client\nFor Example: C:/Riot Games/League of Legends")
+            .set_text(SELECT_CLIENT_TEXT)
             .set_type(MessageType::Info)
             .show_alert()?;
-        let path = FileDialog::new()
-            .show_open_single_dir()?
-            .and_then(|path| match is_valid_lcu_path(&path) {
-                true => Some(path),
-                false => None
-            })
-            .and_then(|path|path.to_str().map(|str|str.to_string()));
-        match path {
-            None => {
-                MessageDialog::new()
-                    .set_text("Invalid directory")
-                    .set_type(MessageType::Error)
-                    .show_alert()?;
-                Err(anyhow!("No league directory"))
+        let client_path = match FileDialog::new().show_open_single_dir()? {
+            // Detecting the running client needs `/proc`, see `RiotLockFile::from_process`.
+            None if cfg!(unix) => {
+                log::info!("No install directory selected. Falling back to the running client");
+                None
             }
-            Some(path) => Ok(Self {
-                client_path: path.to_string(),
-                server_url: "127.0.0.1:43257".to_string()
-            })
-        }
+            None => return Err(anyhow!("No league directory")),
+            Some(path) => match path.to_str() {
+                Some(path) if is_valid_lcu_path(path) => Some(path.to_string()),
+                _ => {
+                    MessageDialog::new()
+                        .set_text("Invalid directory")
+                        .set_type(MessageType::Error)
+                        .show_alert()?;
+                    return Err(anyhow!("No league directory"))
+                }
+            }
+        };
+        Ok(Self {
+            client_path,
+            server_url: "127.0.0.1:43257".to_string(),
+            certificate_path: None,
+            provider: StatsProvider::default(),
+            lcu_proxy: None,
+            auto_accept: AutoAcceptConfig::default(),
+            auto_pick_ban: AutoPickBanConfig::default(),
+            import_presets: false
+        })
+    }
+
+    /// Writes `enabled` back to the config file, so toggling auto accept from the tray survives a restart.
+    /// The file is read again to keep changes made to it since the start.
+    pub fn save_auto_accept(enabled: bool) -> Result<()> {
+        let config_path = config_path()?;
+        let mut config = Self::load(&config_path)?;
+        config.auto_accept.enabled = enabled;
+        config.save(&config_path)
     }
 
     fn save<P:  AsRef<Path>>(&self, path: P) -> Result<()> {
@@ -68,12 +183,34 @@ impl Config {
 
     fn load<P:  AsRef<Path>>(path: P) -> Result<Self> {
         let config: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
-        ensure!(is_valid_lcu_path(&config.client_path));
+        config.provider.validate()?;
+        if let Some(path) = &config.client_path {
+            ensure!(is_valid_lcu_path(path), "{} is not a League Client install directory", path);
+        }
         Ok(config)
     }
 
 }
 
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>()
+        .map_or(false, |err| err.kind() == io::ErrorKind::NotFound)
+}
+
+fn config_dir() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("com.github", "sidit77", "tiny-champ-select-helper").err()?;
+    Ok(dirs.config_dir().to_path_buf())
+}
+
+fn config_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("config.json"))
+}
+
+/// The location of the champion presets, next to `config.json`.
+pub fn presets_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("presets.json"))
+}
+
 fn is_valid_lcu_path<P:  AsRef<Path>>(path: P) -> bool {
     let path = path.as_ref();
     path.join("Config").exists() &&
@@ -0,0 +1,397 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{bail, Result};
+use async_broadcast::{Sender, TrySendError};
+use async_std::channel::{self, Receiver};
+use async_native_tls::Certificate;
+use async_std::prelude::FutureExt;
+use async_std::sync::RwLock;
+use async_std::{fs, task};
+use futures::{stream, FutureExt as _, StreamExt};
+use schemars::JsonSchema;
+use serde::Serialize;
+use surf::Client;
+use crate::champ_select::ChampSelectSession;
+use crate::client_state::{ClientState, ClientStatus, GameflowPhase, Summoner};
+use crate::history::PlayerHistory;
+use crate::lcu::{endpoints, LcuEvent, LcuEventKind, LcuWebSocket, LockfileChange, LockfileWatcher, RiotLockFile, Subscription};
+use crate::relay::{EventRelay, RelayCommand};
+
+/// The endpoints whose events are reflected in the [`ClientStatus`].
+const OBSERVED_ENDPOINTS: &[&str] = &[endpoints::GAMEFLOW_PHASE, endpoints::CHAMP_SELECT_SESSION];
+
+/// The REST client of the current connection, `None` while no client is connected.
+pub type SharedClient = Arc<RwLock<Option<Client>>>;
+
+/// The match histories of a team, loaded in the background.
+type LoadedHistory = (Vec<Summoner>, Vec<PlayerHistory>);
+
+/// Whatever [`ConnectionManager::handle_events`] has to react to next.
+enum Input {
+    Event(Option<Result<LcuEvent>>),
+    Lockfile(Option<notify::Result<LockfileChange>>),
+    Command(Option<RelayCommand>),
+    History(Option<LoadedHistory>)
+}
+
+/// The state of the connection to the client, e.g. `{"state": "Backoff", "attempt": 3, "delay_ms": 4000}`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "state")]
+pub enum ConnectionState {
+    /// Waiting for the client to start or establishing a connection to it.
+    Connecting,
+    Connected,
+    /// The last attempt failed; the next one starts after `delay_ms`.
+    Backoff {
+        attempt: u32,
+        delay_ms: u64
+    }
+}
+
+impl Default for ConnectionState {
+    fn default() -> Self {
+        Self::Connecting
+    }
+}
+
+/// Exponentially growing delays between connection attempts.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    attempt: u32
+}
+
+impl Backoff {
+
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            attempt: 0
+        }
+    }
+
+    /// The number of failed attempts since the last [`reset`](Self::reset).
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Registers a failed attempt and returns how long to wait before the next one.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.initial
+            .checked_mul(1 << self.attempt.min(16))
+            .map_or(self.max, |delay| delay.min(self.max));
+        self.attempt += 1;
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500), Duration::from_secs(30))
+    }
+}
+
+/// Keeps a connection to the client alive and broadcasts every change of the [`ClientStatus`].
+///
+/// Failed connection attempts are retried with exponential backoff until all receivers of the
+/// status channel are gone.
+pub struct ConnectionManager {
+    lockfile_path: Option<PathBuf>,
+    certificate: Option<Certificate>,
+    sender: Sender<(u64, ClientStatus)>,
+    published: u64,
+    client: SharedClient,
+    events: Sender<LcuEvent>,
+    relay: EventRelay,
+    commands: Receiver<RelayCommand>,
+    histories: (channel::Sender<LoadedHistory>, Receiver<LoadedHistory>),
+    /// The team whose match histories are being loaded.
+    loading_history: Option<Vec<Summoner>>,
+    status: ClientStatus,
+    subscriptions: Vec<Subscription>,
+    backoff: Backoff
+}
+
+impl ConnectionManager {
+
+    /// Creates a manager that reads the credentials from the lockfile at `lockfile_path` or,
+    /// if the client is found earlier, from the command line of the running client process.
+    ///
+    /// Every status is sent with a number that increases by one with each change, so receivers can tell
+    /// whether they missed or already saw a status. Slow receivers lose the oldest statuses, the manager
+    /// never waits for them.
+    pub fn new(lockfile_path: Option<PathBuf>, mut sender: Sender<(u64, ClientStatus)>) -> Self {
+        sender.set_overflow(true);
+        let (command_sender, commands) = async_std::channel::unbounded();
+        let (mut events, event_receiver) = async_broadcast::broadcast(64);
+        events.set_overflow(true);
+        Self {
+            lockfile_path,
+            certificate: None,
+            sender,
+            published: 0,
+            client: SharedClient::default(),
+            events,
+            relay: EventRelay::new(command_sender, event_receiver.deactivate()),
+            commands,
+            histories: channel::unbounded(),
+            loading_history: None,
+            status: ClientStatus::default(),
+            subscriptions: Vec::new(),
+            backoff: Backoff::default()
+        }
+    }
+
+    /// Trusts `certificate` instead of the Riot root certificate, see [`RiotLockFile::connect_with_certificate`].
+    pub fn with_certificate(mut self, certificate: Option<Certificate>) -> Self {
+        self.certificate = certificate;
+        self
+    }
+
+    /// A handle to the REST client of the current connection, for making requests outside of the manager.
+    pub fn client(&self) -> SharedClient {
+        self.client.clone()
+    }
+
+    /// A handle for sharing the event websocket of the connection.
+    pub fn relay(&self) -> EventRelay {
+        self.relay.clone()
+    }
+
+    pub async fn run(mut self) {
+        while !self.sender.is_closed() {
+            if let Err(err) = self.session().await {
+                let delay = self.backoff.next_delay();
+                log::warn!("Connection failed. Retrying in {:?}...\n{}", delay, err);
+                self.status = ClientStatus::default();
+                self.status.connection = ConnectionState::Backoff {
+                    attempt: self.backoff.attempt(),
+                    delay_ms: delay.as_millis() as u64
+                };
+                self.publish().ok();
+                task::sleep(delay).await;
+            }
+        }
+    }
+
+    fn publish(&mut self) -> Result<()> {
+        self.published += 1;
+        match self.sender.try_broadcast((self.published, self.status.clone())) {
+            Ok(_) | Err(TrySendError::Inactive(_)) => Ok(()),
+            Err(TrySendError::Closed(_)) => bail!("status channel closed"),
+            // Overflow is enabled in `new`, so the oldest status is dropped instead.
+            Err(TrySendError::Full(_)) => Ok(())
+        }
+    }
+
+    async fn update(&mut self, client: &Client, phase: GameflowPhase) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            match self.status.update(client, phase.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(err) if attempt < 5 => log::error!("Error occurred. Retrying...\n{}", err),
+                Err(err) => return Err(err)
+            }
+            attempt += 1;
+            task::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    async fn find_client(&self) -> Result<RiotLockFile> {
+        let from_process = async {
+            loop {
+                match RiotLockFile::from_process().await {
+                    Some(lockfile) => return Ok::<_, anyhow::Error>(lockfile),
+                    None => task::sleep(Duration::from_secs(2)).await
+                }
+            }
+        };
+        match self.watched_lockfile() {
+            Some(path) => RiotLockFile::read(path)
+                .race(from_process)
+                .await,
+            None => from_process.await
+        }
+    }
+
+    /// The configured lockfile path, if its directory exists and can be watched.
+    fn watched_lockfile(&self) -> Option<&PathBuf> {
+        self.lockfile_path
+            .as_ref()
+            .filter(|path| path.parent().map_or(false, |dir| dir.exists()))
+    }
+
+    async fn session(&mut self) -> Result<()> {
+        self.status.connection = ConnectionState::Connecting;
+        self.publish()?;
+
+        let lockfile = self.find_client().await?;
+        log::info!("found client");
+        let (client, mut socket) = match &self.certificate {
+            Some(cert) => lockfile.connect_with_certificate(cert.clone()).await?,
+            None => lockfile.connect().await?
+        };
+
+        socket.restore(self.subscriptions.clone()).await?;
+        for endpoint in OBSERVED_ENDPOINTS {
+            socket.subscribe(endpoint).await?;
+        }
+
+        let phase = GameflowPhase::load_from(&client).await?;
+        self.update(&client, phase).await?;
+        self.status.connection = ConnectionState::Connected;
+        self.backoff.reset();
+        self.publish()?;
+
+        *self.client.write().await = Some(client.clone());
+        let result = self.handle_events(&client, &lockfile, &mut socket).await;
+        *self.client.write().await = None;
+        self.subscriptions = socket.subscriptions().cloned().collect();
+        result?;
+
+        self.status.close();
+        self.status.connection = ConnectionState::Connecting;
+        self.publish()
+    }
+
+    async fn handle_events(&mut self, client: &Client, lockfile: &RiotLockFile, socket: &mut LcuWebSocket) -> Result<()> {
+        let lockfile_path = self.watched_lockfile().cloned();
+        let mut changes = match &lockfile_path {
+            Some(path) => LockfileWatcher::new(path)?.boxed(),
+            None => stream::pending().boxed()
+        };
+        loop {
+            self.load_history(client);
+            let input = socket.next().map(Input::Event)
+                .race(changes.next().map(Input::Lockfile))
+                .race(self.commands.next().map(Input::Command))
+                .race(self.histories.1.next().map(Input::History))
+                .await;
+            match input {
+                Input::Event(event) => match event {
+                    Some(Ok(event)) => {
+                        self.events.try_broadcast(event.clone()).ok();
+                        self.handle_event(client, event).await?
+                    },
+                    Some(Err(err)) => log::warn!("{}", err),
+                    None => return Ok(())
+                },
+                Input::Command(command) => match command {
+                    Some(RelayCommand::Subscribe(endpoint)) => socket.subscribe(endpoint).await?,
+                    Some(RelayCommand::Unsubscribe(endpoint)) => if !OBSERVED_ENDPOINTS.contains(&endpoint.as_str()) {
+                        socket.unsubscribe(endpoint).await?
+                    },
+                    Some(RelayCommand::Refresh) => {
+                        log::info!("Refreshing status");
+                        self.status = ClientStatus {
+                            connection: self.status.connection,
+                            ..ClientStatus::default()
+                        };
+                        let phase = GameflowPhase::load_from(client).await?;
+                        self.update(client, phase).await?;
+                        self.publish()?
+                    },
+                    None => {}
+                },
+                Input::History(history) => if let Some((players, history)) = history {
+                    if self.loading_history.as_ref() == Some(&players) {
+                        self.loading_history = None;
+                    }
+                    if self.status.set_history(&players, history) {
+                        self.publish()?;
+                    }
+                },
+                Input::Lockfile(change) => match change.transpose()? {
+                    Some(LockfileChange::Removed) => {
+                        log::info!("Lockfile removed");
+                        return Ok(())
+                    },
+                    // Read the file only once: waiting for it to reappear would stall the events of the socket.
+                    Some(LockfileChange::Created | LockfileChange::Modified) => if let Some(path) = &lockfile_path {
+                        match fs::read_to_string(path).await {
+                            Ok(contents) => match contents.parse::<RiotLockFile>() {
+                                Ok(changed) => if !changed.same_credentials(lockfile) {
+                                    log::info!("Lockfile changed");
+                                    return Ok(())
+                                },
+                                Err(err) if err.is_incomplete() => log::debug!("{}. Waiting for the next change...", err),
+                                Err(err) => return Err(err.into())
+                            },
+                            Err(err) if err.kind() == ErrorKind::NotFound => {
+                                log::info!("Lockfile removed");
+                                return Ok(())
+                            },
+                            Err(err) => return Err(err.into())
+                        }
+                    },
+                    None => bail!("Lockfile watcher stopped unexpectedly")
+                }
+            }
+        }
+    }
+
+    /// Loads the match histories of a new team in the background, as they take a while.
+    /// The status is published again once they are done.
+    fn load_history(&mut self, client: &Client) {
+        let players = match self.status.missing_history() {
+            Some(players) if self.loading_history.as_deref() != Some(players) => players.to_vec(),
+            _ => return
+        };
+        let local_puuid = self.status.info
+            .as_ref()
+            .map_or_else(String::new, |info| info.puuid.clone());
+        let (client, sender) = (client.clone(), self.histories.0.clone());
+        self.loading_history = Some(players.clone());
+        task::spawn(async move {
+            let history = PlayerHistory::load_team(&client, &players, &local_puuid).await;
+            sender.send((players, history)).await.ok();
+        });
+    }
+
+    async fn handle_event(&mut self, client: &Client, event: LcuEvent) -> Result<()> {
+        match event.uri.as_str() {
+            endpoints::GAMEFLOW_PHASE => match event.data.as_str() {
+                Some(phase) => {
+                    let phase = GameflowPhase::from(phase);
+                    if phase != self.status.phase {
+                        self.update(client, phase).await?;
+                        self.publish()?;
+                    }
+                },
+                None => log::warn!("Invalid data")
+            },
+            endpoints::CHAMP_SELECT_SESSION => {
+                let session = match event.kind {
+                    LcuEventKind::Delete => None,
+                    _ => match serde_json::from_value::<ChampSelectSession>(event.data) {
+                        Ok(session) => Some(session),
+                        Err(err) => {
+                            log::warn!("Invalid data: {}", err);
+                            return Ok(())
+                        }
+                    }
+                };
+                if self.status.state == ClientState::ChampSelect && self.status.champ_select != session {
+                    // The session is updated every second, so a failed lookup is simply retried with the next one.
+                    if let Err(err) = self.status.update_champ_select(client, session).await {
+                        log::warn!("Could not update champ select: {}", err);
+                        return Ok(())
+                    }
+                    self.publish()?;
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+}
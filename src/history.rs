@@ -0,0 +1,235 @@
+use std::cmp::Reverse;
+use anyhow::Result;
+use error_tools::WrapError;
+use futures::future;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use surf::Client;
+use crate::champ_select::ChampSelectSession;
+use crate::client_state::Summoner;
+use crate::lcu::endpoints;
+
+/// The number of games that are taken into account.
+const RECENT_GAMES: usize = 20;
+
+#[derive(Debug, Deserialize)]
+struct MatchHistory {
+    games: GameList
+}
+
+#[derive(Debug, Deserialize)]
+struct GameList {
+    games: Vec<Game>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Game {
+    game_id: u64,
+    /// Only contains the owner of the match history.
+    participants: Vec<Participant>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Participant {
+    champion_id: i64,
+    stats: ParticipantStats
+}
+
+#[derive(Debug, Deserialize)]
+struct ParticipantStats {
+    win: bool
+}
+
+/// The mastery of a player on a single champion.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChampionMastery {
+    champion_id: i64,
+    champion_level: u32,
+    champion_points: u64
+}
+
+/// A single game of a match history, reduced to what [`PlayerHistory`] needs.
+#[derive(Debug, Clone, PartialEq)]
+struct RecentGame {
+    game_id: u64,
+    champion_id: i64,
+    win: bool
+}
+
+/// A summary of the recent games of a player.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct PlayerHistory {
+    pub puuid: String,
+    pub games: usize,
+    pub wins: usize,
+    /// The number of wins (positive) or losses (negative) in a row up to the latest game.
+    pub streak: i32,
+    /// The champion the player picked or hovers, `0` if there is none.
+    pub champion_id: i64,
+    pub champion_games: usize,
+    pub champion_wins: usize,
+    /// The mastery level and points of the player on `champion_id`, `0` if unknown.
+    pub champion_level: u32,
+    pub champion_points: u64,
+    /// The number of recent games the player played together with the local player.
+    /// Always `0` for the local player.
+    pub games_with_local_player: usize,
+    #[serde(skip)]
+    recent: Vec<RecentGame>,
+    #[serde(skip)]
+    masteries: Vec<ChampionMastery>
+}
+
+impl PlayerHistory {
+
+    /// Loads the recent games and champion masteries of the player with `puuid`.
+    pub async fn load_from(client: &Client, puuid: &str) -> Result<Self> {
+        let masteries = client
+            .get(format!("{}/{}/champion-mastery", endpoints::CHAMPION_MASTERY, puuid))
+            .recv_json::<Vec<ChampionMastery>>()
+            .await.wrapped()
+            .unwrap_or_else(|err| {
+                log::warn!("Could not load champion mastery: {}", err);
+                Vec::new()
+            });
+        let history = client
+            .get(format!("{}/{}/matches?begIndex=0&endIndex={}", endpoints::MATCH_HISTORY, puuid, RECENT_GAMES))
+            .recv_json::<MatchHistory>()
+            .await.wrapped()?;
+        let recent = history.games.games
+            .into_iter()
+            .filter_map(|game| game.participants.first().map(|participant| RecentGame {
+                game_id: game.game_id,
+                champion_id: participant.champion_id,
+                win: participant.stats.win
+            }))
+            .collect();
+        Ok(Self::summarize(puuid, recent, masteries))
+    }
+
+    /// Calculates the statistics of the player with `puuid` from their recent games.
+    fn summarize(puuid: &str, mut recent: Vec<RecentGame>, masteries: Vec<ChampionMastery>) -> Self {
+        // Game ids are increasing, so this puts the latest game first.
+        recent.sort_by_key(|game| Reverse(game.game_id));
+
+        let latest = recent.first().map(|game| game.win);
+        let streak = recent
+            .iter()
+            .take_while(|game| Some(game.win) == latest)
+            .count() as i32;
+        Self {
+            puuid: puuid.to_string(),
+            games: recent.len(),
+            wins: recent.iter().filter(|game| game.win).count(),
+            streak: if latest == Some(false) { -streak } else { streak },
+            games_with_local_player: 0,
+            champion_id: 0,
+            champion_games: 0,
+            champion_wins: 0,
+            champion_level: 0,
+            champion_points: 0,
+            recent,
+            masteries
+        }
+    }
+
+    /// Loads the histories of all `players` and compares them with the one of the local player.
+    /// Players whose history can't be loaded are skipped.
+    pub async fn load_team(client: &Client, players: &[Summoner], local_puuid: &str) -> Vec<Self> {
+        let histories = future::join_all(players
+            .iter()
+            .filter(|player| !player.puuid.is_empty())
+            .map(|player| Self::load_from(client, &player.puuid)))
+            .await;
+        let mut histories: Vec<Self> = histories
+            .into_iter()
+            .filter_map(|history| history
+                .map_err(|err| log::warn!("Could not load match history: {}", err))
+                .ok())
+            .collect();
+        Self::count_games_with_local_player(&mut histories, local_puuid);
+        histories
+    }
+
+    /// Counts the games every other player in `histories` shares with the local player, if their history is known.
+    fn count_games_with_local_player(histories: &mut [Self], local_puuid: &str) {
+        if let Some(local) = histories.iter().find(|history| history.puuid == local_puuid).cloned() {
+            for history in histories.iter_mut().filter(|history| history.puuid != local_puuid) {
+                history.games_with_local_player = history.recent
+                    .iter()
+                    .filter(|game| local.recent.iter().any(|other| other.game_id == game.game_id))
+                    .count();
+            }
+        }
+    }
+
+    /// Recalculates the statistics for the champion the player currently picks or hovers.
+    pub fn set_champion(&mut self, champion_id: i64) {
+        let games = self.recent
+            .iter()
+            .filter(|game| champion_id > 0 && game.champion_id == champion_id);
+        self.champion_id = champion_id;
+        self.champion_games = games.clone().count();
+        self.champion_wins = games.filter(|game| game.win).count();
+        let mastery = self.masteries
+            .iter()
+            .find(|mastery| champion_id > 0 && mastery.champion_id == champion_id);
+        self.champion_level = mastery.map_or(0, |mastery| mastery.champion_level);
+        self.champion_points = mastery.map_or(0, |mastery| mastery.champion_points);
+    }
+
+    /// Updates the champion statistics of every player in `histories` from the matching cell of `session`.
+    pub fn update_champions(histories: &mut [Self], session: &ChampSelectSession) {
+        for member in session.my_team.iter().filter(|member| !member.puuid.is_empty()) {
+            if let Some(history) = histories.iter_mut().find(|history| history.puuid == member.puuid) {
+                let champion_id = match member.champion_id {
+                    0 => member.champion_pick_intent,
+                    champion_id => champion_id
+                };
+                history.set_champion(champion_id);
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn games(games: &[(u64, bool)]) -> Vec<RecentGame> {
+        games
+            .iter()
+            .map(|&(game_id, win)| RecentGame { game_id, champion_id: 1, win })
+            .collect()
+    }
+
+    #[test]
+    fn counts_loss_streaks_from_the_latest_game() {
+        let history = PlayerHistory::summarize("a", games(&[(1, true), (4, false), (2, true), (3, false), (5, false)]), Vec::new());
+        assert_eq!(history.games, 5);
+        assert_eq!(history.wins, 2);
+        assert_eq!(history.streak, -3);
+    }
+
+    #[test]
+    fn summarizes_empty_histories() {
+        let history = PlayerHistory::summarize("a", Vec::new(), Vec::new());
+        assert_eq!((history.games, history.wins, history.streak), (0, 0, 0));
+    }
+
+    #[test]
+    fn counts_shared_games_with_the_local_player() {
+        let mut histories = vec![
+            PlayerHistory::summarize("local", games(&[(1, true), (2, false), (3, true)]), Vec::new()),
+            PlayerHistory::summarize("friend", games(&[(2, false), (3, true), (7, true)]), Vec::new()),
+            PlayerHistory::summarize("stranger", games(&[(4, true), (5, true)]), Vec::new())
+        ];
+        PlayerHistory::count_games_with_local_player(&mut histories, "local");
+        let shared: Vec<_> = histories.iter().map(|history| history.games_with_local_player).collect();
+        assert_eq!(shared, [0, 2, 0]);
+    }
+}
@@ -1,37 +1,154 @@
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::future::ready;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
-use anyhow::{anyhow, Result, Context};
+use anyhow::{anyhow, Result};
 use async_native_tls::{Certificate, TlsConnector};
 use async_std::{fs, task};
 use async_tungstenite::async_std::{connect_async_with_tls_connector, ConnectStream};
 use async_tungstenite::tungstenite::Message;
 use async_tungstenite::WebSocketStream;
 use error_tools::IgnoreResult;
-use futures::{SinkExt, StreamExt};
+use futures::{SinkExt, Stream, StreamExt};
 use http::Request;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher, EventKind};
 use surf::{Client, Config};
 use surf::http::auth::BasicAuth;
+use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
 use serde_repr::{Serialize_repr, Deserialize_repr};
-use crate::Asset;
 
+/// Uris of the client endpoints used by the helper.
+pub mod endpoints {
+    pub const REGION_LOCALE: &str = "/riotclient/region-locale";
+    pub const CURRENT_SUMMONER: &str = "/lol-summoner/v1/current-summoner";
+    pub const GAMEFLOW_PHASE: &str = "/lol-gameflow/v1/gameflow-phase";
+    pub const CHAMP_SELECT_SESSION: &str = "/lol-champ-select/v1/session";
+    pub const CHAMP_SELECT_ACTIONS: &str = "/lol-champ-select/v1/session/actions";
+    pub const PICKABLE_CHAMPIONS: &str = "/lol-champ-select/v1/pickable-champion-ids";
+    pub const BANNABLE_CHAMPIONS: &str = "/lol-champ-select/v1/bannable-champion-ids";
+    pub const MY_SELECTION: &str = "/lol-champ-select/v1/session/my-selection";
+    pub const PERK_PAGES: &str = "/lol-perks/v1/pages";
+    /// Followed by `/{summonerId}/sets`.
+    pub const ITEM_SETS: &str = "/lol-item-sets/v1/item-sets";
+    /// Followed by `/{puuid}/matches`.
+    pub const MATCH_HISTORY: &str = "/lol-match-history/v1/products/lol";
+    /// Followed by `/{puuid}/champion-mastery`.
+    pub const CHAMPION_MASTERY: &str = "/lol-champion-mastery/v1";
+    pub const READY_CHECK: &str = "/lol-matchmaking/v1/ready-check";
+    pub const READY_CHECK_ACCEPT: &str = "/lol-matchmaking/v1/ready-check/accept";
+}
+
+/// The root certificate used by the League Client to sign its local https endpoints.
+pub const RIOT_CERTIFICATE: &[u8] = include_bytes!("../assets/riotgames.pem");
+
+/// The reasons a lockfile can fail to parse.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LockfileError {
+    /// The file ended before the named field. This usually means that the client is still writing it.
+    MissingField(&'static str),
+    InvalidPid(String),
+    InvalidPort(String),
+    /// The protocol is cut off, e.g. `htt`. Like a missing field, this happens while the client is still writing the file.
+    TruncatedProtocol(String),
+    UnknownProtocol(String)
+}
+
+impl LockfileError {
+    /// Checks if the error could be caused by reading a partially written file.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, LockfileError::MissingField(_) | LockfileError::TruncatedProtocol(_))
+    }
+}
+
+impl Display for LockfileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockfileError::MissingField(field) => write!(f, "lockfile is missing the {} field", field),
+            LockfileError::InvalidPid(pid) => write!(f, "lockfile contains an invalid pid: {:?}", pid),
+            LockfileError::InvalidPort(port) => write!(f, "lockfile contains an invalid port: {:?}", port),
+            LockfileError::TruncatedProtocol(protocol) => write!(f, "lockfile contains a truncated protocol: {:?}", protocol),
+            LockfileError::UnknownProtocol(protocol) => write!(f, "lockfile contains an unknown protocol: {:?}", protocol)
+        }
+    }
+}
+
+impl std::error::Error for LockfileError {}
+
+/// The connection details of a running League Client as stored in its `lockfile`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct RiotLockFile {
     pub process: String,
     pub pid: u32,
-    pub port: u32,
+    pub port: u16,
     pub password: String,
     pub protocol: String,
     pub username: String,
     pub address: String,
 }
 
+impl FromStr for RiotLockFile {
+    type Err = LockfileError;
+
+    /// Parses the contents of a lockfile, which look like `LeagueClient:1234:56789:password:https`.
+    ///
+    /// The password is everything between the port and the last `:`, so it may contain `:` itself.
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        let mut pieces = contents.trim_end().splitn(4, ':');
+        let mut next = |field| pieces
+            .next()
+            .filter(|piece| !piece.is_empty())
+            .ok_or(LockfileError::MissingField(field));
+
+        let process = next("process")?.to_string();
+        let pid = next("pid")?;
+        let pid = pid.parse::<u32>().map_err(|_| LockfileError::InvalidPid(pid.to_string()))?;
+        let port = next("port")?;
+        let port = match port.parse::<u16>() {
+            Ok(port) if port != 0 => port,
+            _ => return Err(LockfileError::InvalidPort(port.to_string()))
+        };
+        let (password, protocol) = next("password")?
+            .rsplit_once(':')
+            .ok_or(LockfileError::MissingField("protocol"))?;
+        if password.is_empty() {
+            return Err(LockfileError::MissingField("password"))
+        }
+        let protocol = match protocol {
+            "https" => protocol.to_string(),
+            "" => return Err(LockfileError::MissingField("protocol")),
+            protocol if "https".starts_with(protocol) => return Err(LockfileError::TruncatedProtocol(protocol.to_string())),
+            protocol => return Err(LockfileError::UnknownProtocol(protocol.to_string()))
+        };
+
+        Ok(Self {
+            process,
+            pid,
+            port,
+            password: password.to_string(),
+            protocol,
+            username: "riot".to_string(),
+            address: "127.0.0.1".to_string()
+        })
+    }
+}
 
 impl RiotLockFile {
 
+    /// Checks if both describe the same running client, regardless of how they were obtained.
+    pub fn same_credentials(&self, other: &Self) -> bool {
+        self.port == other.port && self.password == other.password
+    }
+
+    /// Reads the lockfile at `path`, waiting for the client to create it if it doesn't exist yet.
+    ///
+    /// Partially written files are read again a few times before giving up.
     pub async fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
         if !path.exists() {
@@ -39,32 +156,77 @@ impl RiotLockFile {
             task::sleep(Duration::from_secs(5)).await
         }
 
-        let contents = fs::read_to_string(path).await?;
+        let mut attempt = 0;
+        loop {
+            match fs::read_to_string(path).await?.parse::<Self>() {
+                Err(err) if err.is_incomplete() && attempt < 10 => log::debug!("{}. Retrying...", err),
+                result => return Ok(result?)
+            }
+            attempt += 1;
+            task::sleep(Duration::from_millis(250)).await;
+        }
+    }
+
+    /// Looks for a running `LeagueClientUx` process and takes the connection details from its command line.
+    ///
+    /// This only works on systems that expose `/proc`, like Linux when running the client through Wine.
+    pub async fn from_process() -> Option<Self> {
+        let mut entries = fs::read_dir("/proc").await.ok()?;
+        while let Some(entry) = entries.next().await {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue
+            };
+            let pid = match entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+                Some(pid) => pid,
+                None => continue
+            };
+            if let Ok(cmdline) = fs::read(entry.path().join("cmdline")).await {
+                if let Some(lockfile) = Self::from_command_line(pid, &cmdline) {
+                    return Some(lockfile)
+                }
+            }
+        }
+        None
+    }
 
-        let pieces: Vec<&str> = contents.split(':').collect();
+    fn from_command_line(pid: u32, cmdline: &[u8]) -> Option<Self> {
+        let cmdline = String::from_utf8_lossy(cmdline);
+        let mut args = cmdline.split('\0');
+        if !args.next()?.contains("LeagueClientUx") {
+            return None
+        }
 
-        let username = "riot".to_string();
-        let address = "127.0.0.1".to_string();
-        let process = pieces[0].to_string();
-        let pid = pieces[1].parse()?;
-        let port = pieces[2].parse()?;
-        let password = pieces[3].to_string();
-        let protocol = pieces[4].to_string();
+        let mut port = None;
+        let mut password = None;
+        for arg in args {
+            if let Some(value) = arg.strip_prefix("--app-port=") {
+                port = value.parse().ok();
+            } else if let Some(value) = arg.strip_prefix("--remoting-auth-token=") {
+                password = Some(value.to_string());
+            }
+        }
 
-        Ok(Self {
-            process,
+        Some(Self {
+            process: "LeagueClientUx".to_string(),
             pid,
-            port,
-            password,
-            protocol,
-            username,
-            address
+            port: port?,
+            password: password?,
+            protocol: "https".to_string(),
+            username: "riot".to_string(),
+            address: "127.0.0.1".to_string()
         })
     }
 
+    /// Creates an authenticated REST client and opens the event websocket of the client.
     pub async fn connect(&self) -> Result<(Client, LcuWebSocket)> {
+        let cert = Certificate::from_pem(RIOT_CERTIFICATE)?;
+        self.connect_with_certificate(cert).await
+    }
+
+    /// Like [`connect`](Self::connect), but trusts `cert` instead of the Riot root certificate.
+    pub async fn connect_with_certificate(&self, cert: Certificate) -> Result<(Client, LcuWebSocket)> {
         let auth = BasicAuth::new(&self.username, &self.password);
-        let cert = Certificate::from_pem(Asset::get("riotgames.pem").context("can't find cert")?.data.as_ref())?;
 
         let client = Config::new()
             .set_base_url(format!("{}://{}:{}", self.protocol, self.address, self.port).parse()?)
@@ -84,7 +246,8 @@ impl RiotLockFile {
         ).await?;
 
         Ok((client, LcuWebSocket {
-            socket
+            socket,
+            subscriptions: BTreeSet::new()
         }))
     }
 
@@ -92,25 +255,78 @@ impl RiotLockFile {
 
 async fn wait_for<P: AsRef<Path>>(path: P) -> notify::Result<()> {
     let path = path.as_ref();
-    let (tx, mut rx) = async_std::channel::unbounded();
-    let mut watcher = RecommendedWatcher::new(move |event: notify::Result<notify::Event>| tx.try_send(event).ignore())?;
-    watcher.watch(path.parent().unwrap(), RecursiveMode::NonRecursive)?;
-    log::trace!("Started to watch for {:?}", path);
-    while let Some(event) = rx.next().await {
-        match event {
-            Ok(event) => {
-                log::trace!("Got event: {:?}", event);
-                if matches!(event.kind, EventKind::Create(_)) && event.paths.iter().any(|p| p == path) {
-                    log::trace!("Found expected file");
-                    return Ok(())
-                }
-            }
-            Err(e) => return Err(e)
+    let mut watcher = LockfileWatcher::new(path)?;
+    if path.exists() {
+        return Ok(())
+    }
+    while let Some(change) = watcher.next().await {
+        if change? == LockfileChange::Created {
+            log::trace!("Found expected file");
+            return Ok(())
         }
     }
     return Err(notify::Error::generic("Watched stopped unexpectedly"))
 }
 
+/// A change of the lockfile reported by [`LockfileWatcher`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LockfileChange {
+    Created,
+    /// The contents changed, usually because the client restarted with new credentials.
+    Modified,
+    Removed
+}
+
+/// Watches a lockfile for changes, e.g. to notice that the client shut down.
+pub struct LockfileWatcher {
+    path: PathBuf,
+    events: async_std::channel::Receiver<notify::Result<notify::Event>>,
+    _watcher: RecommendedWatcher
+}
+
+impl LockfileWatcher {
+
+    pub fn new<P: AsRef<Path>>(path: P) -> notify::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let (tx, events) = async_std::channel::unbounded();
+        let mut watcher = RecommendedWatcher::new(move |event: notify::Result<notify::Event>| tx.try_send(event).ignore())?;
+        let parent = path.parent().ok_or_else(|| notify::Error::generic("lockfile path has no parent"))?;
+        watcher.watch(parent, RecursiveMode::NonRecursive)?;
+        log::trace!("Started to watch for {:?}", path);
+        Ok(Self {
+            path,
+            events,
+            _watcher: watcher
+        })
+    }
+
+}
+
+impl Stream for LockfileWatcher {
+    type Item = notify::Result<LockfileChange>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let event = match futures::ready!(self.events.poll_next_unpin(cx)) {
+                Some(Ok(event)) => event,
+                Some(Err(err)) => return Poll::Ready(Some(Err(err))),
+                None => return Poll::Ready(None)
+            };
+            log::trace!("Got event: {:?}", event);
+            if !event.paths.iter().any(|p| *p == self.path) {
+                continue
+            }
+            let change = match event.kind {
+                EventKind::Create(_) => LockfileChange::Created,
+                EventKind::Modify(_) if self.path.exists() => LockfileChange::Modified,
+                EventKind::Modify(_) | EventKind::Remove(_) => LockfileChange::Removed,
+                _ => continue
+            };
+            return Poll::Ready(Some(Ok(change)))
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize_repr, Deserialize_repr)]
 #[repr(u32)]
 enum ActionCode {
@@ -123,19 +339,55 @@ enum ActionCode {
 struct Action(ActionCode, String);
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-struct Event(ActionCode, String, EventArgs);
+struct Event(ActionCode, String, LcuEvent);
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
-struct EventArgs {
-    data: Value,
+/// What happened to the resource an event refers to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum LcuEventKind {
+    Create,
+    Update,
+    Delete
+}
+
+/// A change of a client resource pushed over the event websocket.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LcuEvent {
+    pub uri: String,
     #[serde(rename = "eventType")]
-    event_type: String,
-    uri: String
+    pub kind: LcuEventKind,
+    pub data: Value
+}
+
+impl LcuEvent {
+    /// Checks if the uri of this event starts with `prefix`.
+    pub fn matches(&self, prefix: &str) -> bool {
+        self.uri.starts_with(prefix)
+    }
 }
 
+/// An event subscription of the websocket.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Subscription {
+    /// Every event of every endpoint (`OnJsonApiEvent`).
+    All,
+    /// The events of a single endpoint, e.g. `/lol-gameflow/v1/gameflow-phase`.
+    Endpoint(String)
+}
+
+impl Subscription {
+    fn event_name(&self) -> String {
+        match self {
+            Subscription::All => "OnJsonApiEvent".to_string(),
+            Subscription::Endpoint(endpoint) => format!("OnJsonApiEvent{}", endpoint).replace('/', "_")
+        }
+    }
+}
+
+/// The WAMP-style event websocket of the League Client.
 #[derive(Debug)]
 pub struct LcuWebSocket {
-    socket: WebSocketStream<ConnectStream>
+    socket: WebSocketStream<ConnectStream>,
+    subscriptions: BTreeSet<Subscription>
 }
 
 impl LcuWebSocket {
@@ -145,31 +397,224 @@ impl LcuWebSocket {
         Ok(())
     }
 
+    /// Subscribes to the events of `endpoint`, e.g. `/lol-gameflow/v1/gameflow-phase`.
     pub async fn subscribe(&mut self, endpoint: impl AsRef<str>) -> Result<()> {
-        self.send(&Action(ActionCode::Subscribe,
-                          format!("OnJsonApiEvent{}", endpoint.as_ref()).replace('/', "_"))).await
+        self.add_subscription(Subscription::Endpoint(endpoint.as_ref().to_string())).await
+    }
+
+    /// Cancels a previous [`subscribe`](Self::subscribe) call for `endpoint`.
+    pub async fn unsubscribe(&mut self, endpoint: impl AsRef<str>) -> Result<()> {
+        self.remove_subscription(&Subscription::Endpoint(endpoint.as_ref().to_string())).await
+    }
+
+    /// Subscribes to the events of all endpoints.
+    ///
+    /// The client sends an event once for every matching subscription, so events of endpoints
+    /// that are also subscribed individually will be received twice.
+    pub async fn subscribe_all(&mut self) -> Result<()> {
+        self.add_subscription(Subscription::All).await
+    }
+
+    /// Cancels a previous [`subscribe_all`](Self::subscribe_all) call.
+    pub async fn unsubscribe_all(&mut self) -> Result<()> {
+        self.remove_subscription(&Subscription::All).await
+    }
+
+    /// Subscribes to `subscription` unless it is already active.
+    pub async fn add_subscription(&mut self, subscription: Subscription) -> Result<()> {
+        if !self.subscriptions.contains(&subscription) {
+            self.send(&Action(ActionCode::Subscribe, subscription.event_name())).await?;
+            self.subscriptions.insert(subscription);
+        }
+        Ok(())
+    }
+
+    /// Cancels `subscription` if it is active.
+    pub async fn remove_subscription(&mut self, subscription: &Subscription) -> Result<()> {
+        if self.subscriptions.contains(subscription) {
+            self.send(&Action(ActionCode::Unsubscribe, subscription.event_name())).await?;
+            self.subscriptions.remove(subscription);
+        }
+        Ok(())
+    }
+
+    /// Returns all currently active subscriptions.
+    pub fn subscriptions(&self) -> impl Iterator<Item = &Subscription> {
+        self.subscriptions.iter()
+    }
+
+    /// Re-establishes `subscriptions`, usually taken from the socket of a previous connection.
+    pub async fn restore(&mut self, subscriptions: impl IntoIterator<Item = Subscription>) -> Result<()> {
+        for subscription in subscriptions {
+            self.add_subscription(subscription).await?;
+        }
+        Ok(())
+    }
+
+    /// Waits for the next event, or returns `None` if the client closed the connection.
+    pub async fn read(&mut self) -> Result<Option<LcuEvent>> {
+        self.next().await.transpose()
+    }
+
+    /// Returns a stream of the events whose uri starts with `prefix`. Errors are always passed through.
+    pub fn events_with_prefix<'a>(&'a mut self, prefix: &'a str) -> impl Stream<Item = Result<LcuEvent>> + Unpin + 'a {
+        self.filter(move |event| ready(match event {
+            Ok(event) => event.matches(prefix),
+            Err(_) => true
+        }))
     }
 
-    //pub async fn unsubscribe(&mut self, endpoint: impl AsRef<str>) -> Result<()> {
-    //    self.send(&Action(ActionCode::Unsubscribe,
-    //                      format!("OnJsonApiEvent{}", endpoint.as_ref()).replace("/", "_"))).await
-    //}
+    /// Like [`events_with_prefix`](Self::events_with_prefix), but accepts events matching any of `prefixes`.
+    pub fn events_with_prefixes<'a>(&'a mut self, prefixes: &'a [&'a str]) -> impl Stream<Item = Result<LcuEvent>> + Unpin + 'a {
+        self.filter(move |event| ready(match event {
+            Ok(event) => prefixes.iter().any(|prefix| event.matches(prefix)),
+            Err(_) => true
+        }))
+    }
 
-    pub async fn read(&mut self) -> Result<Option<(String, Value)>> {
+}
+
+impl Stream for LcuWebSocket {
+    type Item = Result<LcuEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         loop {
-            let msg = self.socket.next().await.transpose()?;
-
-            match msg {
-                Some(Message::Text(str)) if !str.is_empty() => {
-                    let event = serde_json::from_str::<Event>(&str)?;
-                    return Ok(Some((event.2.uri, event.2.data)))
-                },
-                None => return Ok(None),
-                _ => continue
+            match futures::ready!(self.socket.poll_next_unpin(cx)) {
+                Some(Ok(Message::Text(str))) if !str.is_empty() => return Poll::Ready(Some(serde_json::from_str::<Event>(&str)
+                    .map(|event| event.2)
+                    .map_err(Into::into))),
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                None => return Poll::Ready(None)
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCKFILE: &str = "LeagueClient:1234:56789:pa55word:https";
+
+    #[test]
+    fn parses_lockfile() {
+        let lockfile = LOCKFILE.parse::<RiotLockFile>().unwrap();
+        assert_eq!(lockfile.process, "LeagueClient");
+        assert_eq!(lockfile.pid, 1234);
+        assert_eq!(lockfile.port, 56789);
+        assert_eq!(lockfile.password, "pa55word");
+        assert_eq!(lockfile.protocol, "https");
+        assert_eq!(lockfile.username, "riot");
+        assert_eq!(lockfile.address, "127.0.0.1");
+    }
+
+    #[test]
+    fn ignores_trailing_newlines() {
+        let expected = LOCKFILE.parse::<RiotLockFile>().unwrap();
+        for suffix in ["\n", "\r\n", "\n\n", " \n"] {
+            assert_eq!(format!("{}{}", LOCKFILE, suffix).parse::<RiotLockFile>(), Ok(expected.clone()));
+        }
+    }
+
+    #[test]
+    fn accepts_password_with_colons() {
+        let lockfile = "LeagueClient:1234:56789:pa:ss:word:https".parse::<RiotLockFile>().unwrap();
+        assert_eq!(lockfile.password, "pa:ss:word");
+        assert_eq!(lockfile.protocol, "https");
+    }
+
+    #[test]
+    fn truncated_lockfiles_are_incomplete() {
+        for end in 0..LOCKFILE.len() {
+            let truncated = &LOCKFILE[..end];
+            match truncated.parse::<RiotLockFile>() {
+                Err(err) => assert!(err.is_incomplete(), "{:?} failed with {:?}", truncated, err),
+                Ok(lockfile) => panic!("{:?} parsed as {:?}", truncated, lockfile)
             }
+        }
+    }
 
+    #[test]
+    fn reports_missing_fields() {
+        let cases = [
+            ("", "process"),
+            ("LeagueClient", "pid"),
+            ("LeagueClient:", "pid"),
+            ("LeagueClient:1234", "port"),
+            ("LeagueClient:1234:", "port"),
+            ("LeagueClient:1234:56789", "password"),
+            ("LeagueClient:1234:56789:", "password"),
+            ("LeagueClient:1234:56789::https", "password"),
+            ("LeagueClient:1234:56789:pa55word", "protocol"),
+            ("LeagueClient:1234:56789:pa55word:", "protocol")
+        ];
+        for (contents, field) in cases {
+            assert_eq!(contents.parse::<RiotLockFile>(), Err(LockfileError::MissingField(field)), "{:?}", contents);
         }
+    }
 
+    #[test]
+    fn reports_invalid_fields() {
+        let cases = [
+            ("LeagueClient:abc:56789:pa55word:https", LockfileError::InvalidPid("abc".to_string())),
+            ("LeagueClient:-1:56789:pa55word:https", LockfileError::InvalidPid("-1".to_string())),
+            ("LeagueClient:1234:port:pa55word:https", LockfileError::InvalidPort("port".to_string())),
+            ("LeagueClient:1234:0:pa55word:https", LockfileError::InvalidPort("0".to_string())),
+            ("LeagueClient:1234:65536:pa55word:https", LockfileError::InvalidPort("65536".to_string())),
+            ("LeagueClient:1234:56789:pa55word:htt", LockfileError::TruncatedProtocol("htt".to_string())),
+            ("LeagueClient:1234:56789:pa55word:http", LockfileError::TruncatedProtocol("http".to_string())),
+            ("LeagueClient:1234:56789:pa55word:wss", LockfileError::UnknownProtocol("wss".to_string())),
+            ("LeagueClient:1234:56789:pa55word:httpss", LockfileError::UnknownProtocol("httpss".to_string()))
+        ];
+        for (contents, error) in cases {
+            assert_eq!(contents.parse::<RiotLockFile>(), Err(error), "{:?}", contents);
+        }
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn only_partial_files_are_incomplete() {
+        assert!(LockfileError::MissingField("port").is_incomplete());
+        assert!(LockfileError::TruncatedProtocol("h".to_string()).is_incomplete());
+        assert!(!LockfileError::InvalidPid("x".to_string()).is_incomplete());
+        assert!(!LockfileError::InvalidPort("x".to_string()).is_incomplete());
+        assert!(!LockfileError::UnknownProtocol("x".to_string()).is_incomplete());
+    }
+
+    fn command_line(args: &[&str]) -> Vec<u8> {
+        args.join("\0").into_bytes()
+    }
+
+    #[test]
+    fn parses_client_command_line() {
+        let cmdline = command_line(&[
+            "C:/Riot Games/League of Legends/LeagueClientUx.exe",
+            "--riotclient-auth-token=other",
+            "--app-port=50123",
+            "--remoting-auth-token=t0k3n",
+            ""
+        ]);
+        let lockfile = RiotLockFile::from_command_line(42, &cmdline).unwrap();
+        assert_eq!(lockfile.process, "LeagueClientUx");
+        assert_eq!(lockfile.pid, 42);
+        assert_eq!(lockfile.port, 50123);
+        assert_eq!(lockfile.password, "t0k3n");
+        assert_eq!(lockfile.protocol, "https");
+        assert_eq!(lockfile.username, "riot");
+    }
+
+    #[test]
+    fn rejects_incomplete_command_lines() {
+        let cases = [
+            // The renderer processes carry the token, but not the port.
+            vec!["LeagueClientUxRender.exe", "--type=renderer", "--remoting-auth-token=t0k3n"],
+            vec!["LeagueClientUx.exe", "--app-port=50123"],
+            vec!["LeagueClientUx.exe", "--app-port=port", "--remoting-auth-token=t0k3n"],
+            vec!["LeagueClientUx.exe", "--app-port=", "--remoting-auth-token=t0k3n"],
+            vec!["RiotClientServices.exe", "--app-port=50123", "--remoting-auth-token=t0k3n"],
+            vec![""]
+        ];
+        for args in cases {
+            assert!(RiotLockFile::from_command_line(42, &command_line(&args)).is_none(), "{:?}", args);
+        }
+    }
+}
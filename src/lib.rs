@@ -0,0 +1,44 @@
+//! Building blocks for talking to the League Client (LCU).
+//!
+//! * [`lcu`] finds the client credentials and opens the REST client and the event websocket.
+//! * [`client_state`] loads a summary of what the client is currently doing.
+//! * [`champ_select`] models the champ select session.
+//! * [`history`] summarizes the recent games of the players in champ select.
+//! * [`connection`] keeps a connection to the client alive and broadcasts status changes.
+//! * [`relay`] shares the event websocket of the connection between many consumers.
+//! * [`automation`] acts on behalf of the player, e.g. by accepting ready checks.
+//! * [`presets`] applies per-champion rune pages, summoner spells and item sets.
+//! * [`providers`] builds links to stats sites from the client status.
+//! * [`region`] maps the regions of the client to the names used by other services.
+//! * [`util`] contains helpers for sharing the latest status between tasks.
+//!
+//! ```no_run
+//! use tiny_champ_select_helper::client_state::ClientStatus;
+//! use tiny_champ_select_helper::lcu::{endpoints, RiotLockFile};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let lockfile = RiotLockFile::read("C:/Riot Games/League of Legends/lockfile").await?;
+//! let (client, mut socket) = lockfile.connect().await?;
+//!
+//! let status = ClientStatus::load_from(&client).await?;
+//! println!("{:?}", status);
+//!
+//! socket.subscribe(endpoints::GAMEFLOW_PHASE).await?;
+//! while let Some(event) = socket.read().await? {
+//!     println!("{:?} {}: {}", event.kind, event.uri, event.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod lcu;
+pub mod client_state;
+pub mod champ_select;
+pub mod history;
+pub mod connection;
+pub mod relay;
+pub mod automation;
+pub mod presets;
+pub mod providers;
+pub mod region;
+pub mod util;
@@ -1,98 +1,125 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod lcu;
 mod config;
-mod client_state;
-mod util;
+mod api;
+mod protocol;
+mod proxy;
+mod socket;
 
 use std::path::Path;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use anyhow::{Result};
-use async_broadcast::TrySendError;
+use async_broadcast::Sender;
+use async_native_tls::Certificate;
 use async_std::{task};
+use async_std::sync::RwLock;
 use async_std::prelude::FutureExt as AsyncStdFutureExt;
 use error_tools::IgnoreResult;
 use futures::{FutureExt, StreamExt};
-use futures::future::Either;
 use log::LevelFilter;
+use native_dialog::{MessageDialog, MessageType};
 use tide::{Body, Redirect, Request, Response};
 use tide::http::{mime, Mime};
-use tide_websockets::{Message, WebSocket};
 use tray_item::TrayItem;
 use rust_embed::{EmbeddedFile, RustEmbed};
 use surf::StatusCode;
-use crate::client_state::{ClientState, ClientStatus};
+use tiny_champ_select_helper::automation::{AutoAccept, AutoPickBan};
+use tiny_champ_select_helper::client_state::ClientStatus;
+use tiny_champ_select_helper::connection::ConnectionManager;
+use tiny_champ_select_helper::presets::Presets;
+use tiny_champ_select_helper::providers::StatsProvider;
+use tiny_champ_select_helper::relay::EventRelay;
+use tiny_champ_select_helper::util::ReceiveWrapper;
 use crate::config::Config;
-use crate::lcu::RiotLockFile;
-use crate::util::ReceiveWrapper;
+use crate::proxy::LcuProxy;
 
 #[derive(RustEmbed)]
 #[folder = "assets/"]
 pub struct Asset;
 
-async fn run(config: &Config) -> Result<()> {
-    let (mut sender, receiver) = async_broadcast::broadcast(10);
-
-    let lockfile_path = Path::new(&config.client_path).join("lockfile");
-
-    let _handler = async_std::task::spawn(async move {
-        sender.set_overflow(true);
-
-        'outer: loop {
-            let lockfile = RiotLockFile::read(&lockfile_path).await.unwrap();
-            log::info!("found lockfile");
-            let (client, mut socket) = lockfile.connect().await.unwrap();
-
-            let mut status = loop {
-                match ClientStatus::load_from(&client).await {
-                    Ok(res) => break res,
-                    Err(err) => log::error!("Error occurred. Retrying...\n{}", err)
-                };
-                task::sleep(Duration::from_millis(500)).await;
-            };
-            sender.try_broadcast(status.clone()).ignore();
-
-            socket.subscribe("/lol-gameflow/v1/gameflow-phase").await.unwrap();
-
-            loop {
-                match socket.read().await {
-                    Ok(Some((uri, json))) if uri == "/lol-gameflow/v1/gameflow-phase" => {
-                        match json.as_str() {
-                            Some(state) => {
-                                let state = ClientState::from(state);
-                                if state != status.state {
-                                    //retry(Duration::from_millis(500), || status.update(&client, state)).await;
-                                    loop {
-                                        match status.update(&client, state).await {
-                                            Ok(res) => break res,
-                                            Err(err) => log::error!("Error occurred. Retrying...\n{}", err)
-                                        };
-                                        task::sleep(Duration::from_millis(500)).await;
-                                    }
-                                    match sender.try_broadcast(status.clone()) {
-                                        Ok(_) | Err(TrySendError::Inactive(_)) => {},
-                                        Err(TrySendError::Closed(_)) => break 'outer,
-                                        Err(TrySendError::Full(_)) => unreachable!()
-                                    }
-                                }
-                            },
-                            None => log::warn!("Invalid data")
-                        }
-                    },
-                    Ok(Some(tuple)) => log::warn!("Unknown event: {:?}", tuple),
-                    Ok(None) => break,
-                    Err(err) => log::warn!("{}", err)
-                }
-            }
+#[derive(Clone)]
+struct AppState {
+    status: ReceiveWrapper<ClientStatus>,
+    relay: EventRelay,
+    provider: Arc<RwLock<StatsProvider>>,
+    /// The provider from the config, the only custom one clients may switch to.
+    configured_provider: StatsProvider,
+    /// Distinguishes the status numbers of this process from those of an earlier run.
+    epoch: u128,
+    server_url: String,
+    /// Also decides which LCU events `/socket` clients may subscribe to.
+    proxy: Option<Arc<LcuProxy>>
+}
 
-            status.update(&client, ClientState::Closed).await.unwrap();
-            sender.try_broadcast(status.clone()).ignore();
-        }
+impl AppState {
+    async fn provider(&self) -> StatsProvider {
+        self.provider.read().await.clone()
+    }
+
+    async fn set_provider(&self, provider: StatsProvider) {
+        *self.provider.write().await = provider;
+    }
+
+    async fn url(&self) -> String {
+        self.provider().await.url_for(&self.status.latest().await)
+    }
+
+    /// Whether `req` was addressed to the helper itself and, if it comes from a browser, from a page served by it.
+    /// This keeps other websites, including ones that rebind their domain to loopback, out of privileged endpoints.
+    fn is_same_origin(&self, req: &Request<AppState>) -> bool {
+        let host = req
+            .header("Host")
+            .map_or(false, |host| host.as_str().eq_ignore_ascii_case(&self.server_url));
+        let origin = req
+            .header("Origin")
+            .map_or(true, |origin| origin.as_str().eq_ignore_ascii_case(&format!("http://{}", self.server_url)));
+        host && origin
+    }
+}
 
-    });
+fn connection_manager(config: &Config, sender: Sender<(u64, ClientStatus)>) -> Result<ConnectionManager> {
+    let lockfile_path = config.client_path
+        .as_ref()
+        .map(|path| Path::new(path).join("lockfile"));
+    let certificate = match &config.certificate_path {
+        Some(path) => Some(Certificate::from_pem(&std::fs::read(path)?)?),
+        None => None
+    };
+    Ok(ConnectionManager::new(lockfile_path, sender)
+        .with_certificate(certificate))
+}
+
+async fn run(config: &Config, manager: ConnectionManager, state: AppState, auto_accept: AutoAccept) -> Result<()> {
+    let client = manager.client();
+    let _auto_accept = task::spawn(auto_accept.run(manager.relay(), client.clone()));
+    let _auto_pick_ban = match config.auto_pick_ban.enabled {
+        true => {
+            let auto_pick_ban = AutoPickBan::new(
+                config.auto_pick_ban.priorities.clone(),
+                config.auto_pick_ban.hover_only,
+                Duration::from_millis(config.auto_pick_ban.lock_delay_ms));
+            Some(task::spawn(auto_pick_ban.run(state.status.clone(), client.clone())))
+        },
+        false => None
+    };
+    let _presets = match config.import_presets {
+        true => {
+            let path = crate::config::presets_path()?;
+            match Presets::load(&path) {
+                Ok(presets) => Some(task::spawn(presets.run(state.status.clone(), client.clone()))),
+                Err(err) => {
+                    log::warn!("Could not load presets from {}: {}", path.display(), err);
+                    None
+                }
+            }
+        },
+        false => None
+    };
+    let _handler = task::spawn(manager.run());
 
-    let mut app = tide::with_state(ReceiveWrapper::new(receiver));
-    app.at("*").get(|req: tide::Request<ReceiveWrapper<ClientStatus>> | async move {
+    let mut app = tide::with_state(state);
+    app.at("*").get(|req: tide::Request<AppState> | async move {
         let path = req.url().path().trim_start_matches('/');
         log::debug!("trying to load {}", path);
         let asset: Option<EmbeddedFile> = Asset::get(path);
@@ -117,24 +144,15 @@ async fn run(config: &Config) -> Result<()> {
         }
     });
     app.at("/").get(Redirect::permanent("/index.html"));
-    app.at("/socket").get(WebSocket::new(|req: Request<ReceiveWrapper<ClientStatus>>, mut stream| async move {
-        let (state, mut receiver) = req.state().subscribe().await;
-        stream.send_string(serde_json::to_string(&state)?).await?;
-        loop {
-            match futures::future::select(stream.next(), receiver.next()).await {
-                Either::Left((msg, _)) => match msg {
-                    Some(Ok(Message::Close(_))) => {}
-                    Some(msg) => log::info!("Got unexpected message: {:?}", msg),
-                    None => break
-                }
-                Either::Right((msg, _)) => match msg {
-                    Some(msg) => stream.send_string(serde_json::to_string(&msg)?).await?,
-                    None => break
-                }
-            }
-        }
-        Ok(())
-    }));
+    let api = api::api(app.state().clone());
+    app.at("/api").nest(api);
+    if let Some(proxy) = app.state().proxy.clone() {
+        app.at("/lcu/*path").all(move |req: Request<AppState>| {
+            let proxy = proxy.clone();
+            async move { proxy.forward(req).await }
+        });
+    }
+    app.at("/socket").get(socket::upgrade);
     app.listen(&config.server_url).await?;
     Ok(())
 }
@@ -157,16 +175,56 @@ fn main() -> Result<()> {
         move || webbrowser::open(&format!("http://{}", addrs)).unwrap()
     };
 
+    let (status_sender, status_receiver) = async_broadcast::broadcast(10);
+    let manager = connection_manager(&config, status_sender)?;
+    let state = AppState {
+        status: ReceiveWrapper::new(status_receiver),
+        relay: manager.relay(),
+        provider: Arc::new(RwLock::new(config.provider.clone())),
+        configured_provider: config.provider.clone(),
+        epoch: SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis(),
+        server_url: config.server_url.clone(),
+        proxy: config.lcu_proxy
+            .clone()
+            .map(|proxy_config| Arc::new(LcuProxy::new(proxy_config, manager.client())))
+    };
+
+    let auto_accept = AutoAccept::new(config.auto_accept.enabled, Duration::from_millis(config.auto_accept.delay_ms));
+
     let quitter = async_ctrlc::CtrlC::new()?;
 
     let (sender, mut receiver) = async_std::channel::bounded(2);
     let mut tray = TrayItem::new("Tiny Champ Select Helper", "favicon").unwrap();
     tray.add_menu_item("Open", open.clone()).unwrap();
+    tray.add_menu_item("Open Stats Site", {
+        let state = state.clone();
+        move || {
+            let url = task::block_on(state.url());
+            webbrowser::open(&url).unwrap()
+        }
+    }).unwrap();
+    tray.add_menu_item("Toggle Auto Accept", {
+        let auto_accept = auto_accept.clone();
+        move || {
+            let enabled = auto_accept.toggle();
+            let state = if enabled { "enabled" } else { "disabled" };
+            log::info!("Auto accept {}", state);
+            if let Err(err) = Config::save_auto_accept(enabled) {
+                log::warn!("Could not save the auto accept setting: {}", err);
+            }
+            // tray-item can't change the label of a menu item, so the new state is shown in a dialog instead.
+            MessageDialog::new()
+                .set_text(&format!("Auto accept is now {}", state))
+                .set_type(MessageType::Info)
+                .show_alert()
+                .ignore();
+        }
+    }).unwrap();
     tray.add_menu_item("Quit", move || {
         sender.try_send(()).unwrap();
     }).unwrap();
     let quitter = quitter.race(receiver.next().map(|r|r.unwrap()));
 
     open();
-    task::block_on(run(&config).race(quitter.map(|_ | Ok(()))))
+    task::block_on(run(&config, manager, state, auto_accept).race(quitter.map(|_ | Ok(()))))
 }
\ No newline at end of file
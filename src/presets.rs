@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::path::Path;
+use anyhow::{ensure, Context, Result};
+use error_tools::WrapError;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use surf::Client;
+use crate::champ_select::ChampSelectSession;
+use crate::client_state::ClientStatus;
+use crate::connection::SharedClient;
+use crate::lcu::endpoints;
+use crate::util::ReceiveWrapper;
+
+/// The name of the rune page owned by the helper. It is replaced on every import, all other pages are left alone.
+pub const HELPER_PAGE_NAME: &str = "Tiny Champ Select Helper";
+
+/// The prefix of the uids of the item sets owned by the helper. Like the rune page, they are replaced on every import.
+pub const HELPER_ITEM_SET_PREFIX: &str = "tiny-champ-select-helper-";
+
+/// The loadouts to apply by champion id, usually read from `presets.json` next to `config.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Presets {
+    pub champions: HashMap<i64, ChampionPreset>
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChampionPreset {
+    pub runes: Option<RunePreset>,
+    pub spells: Option<SpellPreset>,
+    pub item_sets: Vec<ItemSetPreset>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunePreset {
+    pub primary_style_id: i64,
+    pub sub_style_id: i64,
+    /// The keystone and all other runes in the order the client shows them, including the stat shards.
+    pub selected_perk_ids: Vec<i64>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpellPreset {
+    pub spell1_id: u64,
+    pub spell2_id: u64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemSetPreset {
+    pub title: String,
+    /// Only export the set when playing this position (`top`, `jungle`, `middle`, `bottom` or `utility`).
+    /// Sets without a position are always exported.
+    #[serde(default)]
+    pub position: Option<String>,
+    pub blocks: Vec<ItemBlock>
+}
+
+/// A titled group of items within an item set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ItemBlock {
+    pub title: String,
+    pub items: Vec<u32>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PerkPage {
+    id: i64,
+    name: String
+}
+
+impl Presets {
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Applies the presets of the champion the local player locks in, once per champ select.
+    pub async fn run(self, status: ReceiveWrapper<ClientStatus>, client: SharedClient) {
+        let (_, mut statuses) = status.subscribe().await;
+        let mut applied = None;
+        while let Some(status) = statuses.next().await {
+            let (session, champion) = match status.champ_select.as_ref().and_then(|session| Some((session, session.locked_champion()?))) {
+                Some(locked) => locked,
+                None => {
+                    if status.champ_select.is_none() {
+                        applied = None;
+                    }
+                    continue
+                }
+            };
+            if applied == Some(champion) {
+                continue
+            }
+            applied = Some(champion);
+            let (preset, client) = match (self.champions.get(&champion), client.read().await.clone()) {
+                (Some(preset), Some(client)) => (preset, client),
+                _ => continue
+            };
+            log::info!("Applying preset for champion {}", champion);
+            if let Err(err) = preset.apply(&client, champion, session).await {
+                log::warn!("Could not apply preset: {}", err);
+            }
+        }
+    }
+
+}
+
+impl ChampionPreset {
+
+    pub async fn apply(&self, client: &Client, champion: i64, session: &ChampSelectSession) -> Result<()> {
+        if let Some(runes) = &self.runes {
+            runes.apply(client).await?;
+        }
+        if let Some(spells) = &self.spells {
+            spells.apply(client).await?;
+        }
+        if !self.item_sets.is_empty() {
+            let player = session.local_player().context("local player is missing from the session")?;
+            let sets = self.item_sets
+                .iter()
+                .filter(|set| set.position.as_ref().map_or(true, |position| *position == player.assigned_position))
+                .enumerate()
+                .map(|(index, set)| set.to_client_format(champion, index))
+                .collect();
+            export_item_sets(client, player.summoner_id, sets).await?;
+        }
+        Ok(())
+    }
+
+}
+
+impl RunePreset {
+
+    /// Replaces the helper page with this preset and makes it the current page.
+    pub async fn apply(&self, client: &Client) -> Result<()> {
+        let pages = client
+            .get(endpoints::PERK_PAGES)
+            .recv_json::<Vec<PerkPage>>()
+            .await.wrapped()?;
+        for page in pages.iter().filter(|page| page.name == HELPER_PAGE_NAME) {
+            let response = client
+                .delete(format!("{}/{}", endpoints::PERK_PAGES, page.id))
+                .await.wrapped()?;
+            ensure!(response.status().is_success(), "deleting the old rune page failed with {}", response.status());
+        }
+        let response = client
+            .post(endpoints::PERK_PAGES)
+            .body_json(&json!({
+                "name": HELPER_PAGE_NAME,
+                "primaryStyleId": self.primary_style_id,
+                "subStyleId": self.sub_style_id,
+                "selectedPerkIds": self.selected_perk_ids,
+                "current": true
+            })).wrapped()?
+            .await.wrapped()?;
+        ensure!(response.status().is_success(), "creating the rune page failed with {}", response.status());
+        Ok(())
+    }
+
+}
+
+impl SpellPreset {
+
+    pub async fn apply(&self, client: &Client) -> Result<()> {
+        let response = client
+            .patch(endpoints::MY_SELECTION)
+            .body_json(&json!({
+                "spell1Id": self.spell1_id,
+                "spell2Id": self.spell2_id
+            })).wrapped()?
+            .await.wrapped()?;
+        ensure!(response.status().is_success(), "setting the summoner spells failed with {}", response.status());
+        Ok(())
+    }
+
+}
+
+impl ItemSetPreset {
+
+    /// Converts the preset into the item set format of the client.
+    pub fn to_client_format(&self, champion: i64, index: usize) -> Value {
+        let blocks: Vec<Value> = self.blocks
+            .iter()
+            .map(|block| json!({
+                "type": block.title,
+                "items": block.items
+                    .iter()
+                    .map(|item| json!({ "id": item.to_string(), "count": 1 }))
+                    .collect::<Vec<_>>()
+            }))
+            .collect();
+        json!({
+            "uid": format!("{}{}-{}", HELPER_ITEM_SET_PREFIX, champion, index),
+            "title": self.title,
+            "type": "custom",
+            "map": "any",
+            "mode": "any",
+            "sortrank": 0,
+            "startedFrom": "blank",
+            "associatedChampions": [champion],
+            "associatedMaps": [],
+            "preferredItemSlots": [],
+            "blocks": blocks
+        })
+    }
+
+}
+
+/// Replaces the item sets of the helper with `sets`, keeping all sets created by the player.
+async fn export_item_sets(client: &Client, summoner_id: u64, sets: Vec<Value>) -> Result<()> {
+    let uri = format!("{}/{}/sets", endpoints::ITEM_SETS, summoner_id);
+    let mut item_sets = client
+        .get(&uri)
+        .recv_json::<Value>()
+        .await.wrapped()?;
+    let existing = item_sets
+        .get_mut("itemSets")
+        .and_then(Value::as_array_mut)
+        .context("invalid item sets")?;
+    existing.retain(|set| !set
+        .get("uid")
+        .and_then(Value::as_str)
+        .map_or(false, |uid| uid.starts_with(HELPER_ITEM_SET_PREFIX)));
+    existing.extend(sets);
+    let response = client
+        .put(&uri)
+        .body_json(&item_sets).wrapped()?
+        .await.wrapped()?;
+    ensure!(response.status().is_success(), "exporting the item sets failed with {}", response.status());
+    Ok(())
+}
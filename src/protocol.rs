@@ -0,0 +1,321 @@
+use schemars::JsonSchema;
+use schemars::schema::RootSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tiny_champ_select_helper::client_state::ClientStatus;
+use tiny_champ_select_helper::lcu::{LcuEvent, LcuEventKind};
+use tiny_champ_select_helper::providers::StatsProvider;
+
+/// The version of the message format used by `/socket` and `/api/events`.
+/// Bumped whenever a message changes in a way that breaks existing clients.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The wrapper around every message sent to clients: `{"version": 1, "type": "status", "payload": {...}}`.
+#[derive(Serialize, JsonSchema)]
+pub struct Envelope<T> {
+    pub version: u32,
+    #[serde(flatten)]
+    pub message: T
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum ServerMessage<'a> {
+    /// The current status. Sent when a client connects and whenever it changes.
+    Status(StatusMessage<'a>),
+    /// An LCU event of an endpoint the client subscribed to.
+    Event(EventMessage<'a>),
+    /// The answer to a [`Command`].
+    Response {
+        id: Option<u64>,
+        #[serde(flatten)]
+        response: Response
+    }
+}
+
+impl ServerMessage<'_> {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&Envelope {
+            version: PROTOCOL_VERSION,
+            message: self
+        })
+    }
+}
+
+/// A status update, along with the stats site that should be shown for it.
+#[derive(Serialize, JsonSchema)]
+pub struct StatusMessage<'a> {
+    #[serde(flatten)]
+    pub status: &'a ClientStatus,
+    pub url: String
+}
+
+impl<'a> StatusMessage<'a> {
+    pub fn new(status: &'a ClientStatus, provider: &StatsProvider) -> Self {
+        Self {
+            status,
+            url: provider.url_for(status)
+        }
+    }
+}
+
+/// An LCU event as sent to clients. Unlike [`LcuEvent`], which mirrors the format of the client,
+/// it names its fields like every other message.
+#[derive(Serialize, JsonSchema)]
+pub struct EventMessage<'a> {
+    pub uri: &'a str,
+    pub kind: LcuEventKind,
+    pub data: &'a Value
+}
+
+impl<'a> From<&'a LcuEvent> for EventMessage<'a> {
+    fn from(event: &'a LcuEvent) -> Self {
+        Self {
+            uri: &event.uri,
+            kind: event.kind,
+            data: &event.data
+        }
+    }
+}
+
+/// A command sent by the web page or another websocket client.
+///
+/// The `id` is optional and copied into the response, so clients can match responses to their commands.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ClientMessage {
+    #[serde(default)]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub command: Command
+}
+
+/// Only the `id` of a [`ClientMessage`], so it can be echoed even if the command is invalid.
+#[derive(Debug, Deserialize)]
+pub struct MessageId {
+    #[serde(default)]
+    pub id: Option<u64>
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    Ping,
+    /// Reloads the status from the client.
+    Refresh,
+    /// Opens the current stats site in the default browser.
+    OpenProvider,
+    /// Switches the stats site for all pages until the helper is restarted.
+    SetProvider {
+        provider: StatsProvider
+    },
+    /// Relays the events of the LCU endpoint `endpoint` and everything below it.
+    Subscribe {
+        endpoint: String
+    },
+    Unsubscribe {
+        endpoint: String
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Pong,
+    Url {
+        url: String
+    },
+    Provider {
+        provider: StatsProvider,
+        name: String
+    },
+    Error {
+        message: String
+    }
+}
+
+/// The JSON schema of the messages sent by the server.
+pub fn message_schema() -> RootSchema {
+    schemars::schema_for!(Envelope<ServerMessage<'static>>)
+}
+
+/// The JSON schema of the commands accepted by `/socket`.
+pub fn command_schema() -> RootSchema {
+    schemars::schema_for!(ClientMessage)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::path::Path;
+    use serde_json::{json, Value};
+    use tiny_champ_select_helper::champ_select::ChampSelectSession;
+    use tiny_champ_select_helper::client_state::{BasicInfo, ClientState, GameflowPhase, Summoner};
+    use tiny_champ_select_helper::connection::ConnectionState;
+    use tiny_champ_select_helper::history::PlayerHistory;
+    use tiny_champ_select_helper::lcu::LcuEventKind;
+    use tiny_champ_select_helper::providers::UrlTemplates;
+    use tiny_champ_select_helper::region::Region;
+    use super::*;
+
+    /// Compares `actual` with `tests/snapshots/<name>.json`. Run with `UPDATE_SNAPSHOTS=1` to accept changes.
+    fn assert_snapshot(name: &str, actual: &Value) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(format!("{}.json", name));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, serde_json::to_string_pretty(actual).unwrap() + "\n").unwrap();
+        }
+        let expected: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(actual, &expected, "{} changed", name);
+    }
+
+    fn champ_select_status() -> ClientStatus {
+        let session: ChampSelectSession = serde_json::from_value(json!({
+            "localPlayerCellId": 0,
+            "myTeam": [{
+                "cellId": 0, "championId": 222, "championPickIntent": 0, "assignedPosition": "bottom",
+                "spell1Id": 4, "spell2Id": 7, "summonerId": 1, "puuid": "puuid-1",
+                "gameName": "Name", "tagLine": "TAG", "team": 1
+            }],
+            "theirTeam": [{ "cellId": 5, "team": 2 }],
+            "actions": [[
+                { "id": 1, "actorCellId": 0, "championId": 25, "completed": true, "isAllyAction": true, "isInProgress": false, "type": "ban" },
+                { "id": 7, "actorCellId": 0, "championId": 222, "completed": false, "isAllyAction": true, "isInProgress": true, "type": "pick" }
+            ]],
+            "bans": { "myTeamBans": [25], "theirTeamBans": [], "numBans": 10 },
+            "timer": { "phase": "BAN_PICK", "adjustedTimeLeftInPhase": 27000, "totalTimeInPhase": 30000, "isInfinite": false }
+        })).unwrap();
+        let player: Summoner = serde_json::from_value(json!({
+            "summonerId": 1, "puuid": "puuid-1", "displayName": "", "gameName": "Name", "tagLine": "TAG"
+        })).unwrap();
+        let mut history = PlayerHistory::default();
+        history.puuid = "puuid-1".to_string();
+        history.games = 20;
+        history.wins = 12;
+        history.streak = -2;
+        history.champion_id = 222;
+        history.champion_games = 5;
+        history.champion_wins = 4;
+        history.champion_level = 7;
+        history.champion_points = 123456;
+        ClientStatus {
+            connection: ConnectionState::Connected,
+            phase: GameflowPhase::ChampSelect,
+            state: ClientState::ChampSelect,
+            info: Some(BasicInfo {
+                server: "euw".to_string(),
+                region: Some(Region::Euw),
+                username: "Name#TAG".to_string(),
+                game_name: "Name".to_string(),
+                tag_line: "TAG".to_string(),
+                puuid: "puuid-1".to_string()
+            }),
+            champ_select: Some(session),
+            players: Some(vec![player]),
+            history: Some(vec![history])
+        }
+    }
+
+    fn messages() -> Vec<(&'static str, Value)> {
+        let backoff = ClientStatus {
+            connection: ConnectionState::Backoff { attempt: 3, delay_ms: 4000 },
+            ..ClientStatus::default()
+        };
+        let champ_select = champ_select_status();
+        let event = LcuEvent {
+            uri: "/lol-gameflow/v1/gameflow-phase".to_string(),
+            kind: LcuEventKind::Update,
+            data: json!("ChampSelect")
+        };
+        let custom = StatsProvider::Custom(UrlTemplates {
+            name: "Dashboard".to_string(),
+            closed: "https://example.com".to_string(),
+            idle: "https://example.com/{region}/{riot_id}".to_string(),
+            champ_select: "https://example.com/multi?names={names_csv}".to_string(),
+            in_game: "https://example.com/live/{puuid}".to_string()
+        });
+        let response = |id, response| ServerMessage::Response { id, response };
+        [
+            ("status_connecting", ServerMessage::Status(StatusMessage::new(&ClientStatus::default(), &StatsProvider::OpGg))),
+            ("status_backoff", ServerMessage::Status(StatusMessage::new(&backoff, &StatsProvider::OpGg))),
+            ("status_champ_select", ServerMessage::Status(StatusMessage::new(&champ_select, &StatsProvider::UGg))),
+            ("event", ServerMessage::Event((&event).into())),
+            ("response_ok", response(Some(1), Response::Ok)),
+            ("response_pong", response(None, Response::Pong)),
+            ("response_url", response(Some(2), Response::Url { url: "https://op.gg".to_string() })),
+            ("response_provider", response(Some(3), Response::Provider { name: "u.gg".to_string(), provider: StatsProvider::UGg })),
+            ("response_provider_custom", response(Some(4), Response::Provider { name: custom.name().to_string(), provider: custom.clone() })),
+            ("response_error", response(Some(5), Response::Error { message: "unknown variant `foo`".to_string() }))
+        ]
+            .into_iter()
+            .map(|(name, message)| (name, serde_json::from_str(&message.to_json().unwrap()).unwrap()))
+            .collect()
+    }
+
+    fn keys(value: &Value, keys: &mut BTreeSet<String>) {
+        match value {
+            Value::Object(map) => for (key, value) in map {
+                keys.insert(key.clone());
+                self::keys(value, keys);
+            },
+            Value::Array(values) => values.iter().for_each(|value| self::keys(value, keys)),
+            _ => {}
+        }
+    }
+
+    fn property_names(schema: &Value, names: &mut BTreeSet<String>) {
+        match schema {
+            Value::Object(map) => for (key, value) in map {
+                if key == "properties" {
+                    names.extend(value.as_object().unwrap().keys().cloned());
+                }
+                property_names(value, names);
+            },
+            Value::Array(values) => values.iter().for_each(|value| property_names(value, names)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn messages_match_snapshots() {
+        for (name, message) in messages() {
+            assert_snapshot(name, &message);
+        }
+    }
+
+    #[test]
+    fn schemas_match_snapshots() {
+        assert_snapshot("message_schema", &serde_json::to_value(message_schema()).unwrap());
+        assert_snapshot("command_schema", &serde_json::to_value(command_schema()).unwrap());
+    }
+
+    /// The schema renames fields separately from serde, so make sure it describes what is actually sent.
+    #[test]
+    fn schema_describes_every_field() {
+        let mut schema = BTreeSet::new();
+        property_names(&serde_json::to_value(message_schema()).unwrap(), &mut schema);
+        for (name, mut message) in messages() {
+            // The event payload is whatever the client sent.
+            if let Some(data) = message.pointer_mut("/payload/data") {
+                *data = Value::Null;
+            }
+            let mut fields = BTreeSet::new();
+            keys(&message, &mut fields);
+            let missing: Vec<_> = fields.difference(&schema).collect();
+            assert!(missing.is_empty(), "{} has fields missing from the schema: {:?}", name, missing);
+        }
+    }
+
+    #[test]
+    fn parses_commands() {
+        let message: ClientMessage = serde_json::from_value(json!({"id": 7, "type": "set_provider", "provider": "u_gg"})).unwrap();
+        assert_eq!(message.id, Some(7));
+        assert!(matches!(message.command, Command::SetProvider { provider: StatsProvider::UGg }));
+        let message: ClientMessage = serde_json::from_value(json!({"type": "subscribe", "endpoint": "/lol-lobby/v2/lobby"})).unwrap();
+        assert_eq!(message.id, None);
+        assert!(matches!(message.command, Command::Subscribe { endpoint } if endpoint == "/lol-lobby/v2/lobby"));
+        let message: MessageId = serde_json::from_value(json!({"id": 8, "type": "jump"})).unwrap();
+        assert_eq!(message.id, Some(8));
+    }
+}
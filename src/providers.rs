@@ -0,0 +1,384 @@
+use std::fmt::{Display, Formatter};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use surf::Url;
+use crate::client_state::{BasicInfo, ClientState, ClientStatus, Summoner};
+
+/// A website that shows statistics about summoners.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsProvider {
+    OpGg,
+    UGg,
+    Porofessor,
+    LeagueOfGraphs,
+    /// A site defined by the user, e.g. an internal dashboard.
+    Custom(UrlTemplates)
+}
+
+impl Default for StatsProvider {
+    fn default() -> Self {
+        Self::OpGg
+    }
+}
+
+impl StatsProvider {
+
+    pub const ALL: [StatsProvider; 4] = [Self::OpGg, Self::UGg, Self::Porofessor, Self::LeagueOfGraphs];
+
+    /// The human readable name of the site.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::OpGg => "op.gg",
+            Self::UGg => "u.gg",
+            Self::Porofessor => "Porofessor",
+            Self::LeagueOfGraphs => "League of Graphs",
+            Self::Custom(templates) => &templates.name
+        }
+    }
+
+    /// Checks that the templates of a custom provider only use placeholders that are available.
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        match self {
+            Self::Custom(templates) => templates.validate(),
+            _ => Ok(())
+        }
+    }
+
+    pub fn home_url(&self) -> String {
+        match self {
+            Self::OpGg => "https://op.gg".to_string(),
+            Self::UGg => "https://u.gg".to_string(),
+            Self::Porofessor => "https://porofessor.gg".to_string(),
+            Self::LeagueOfGraphs => "https://www.leagueofgraphs.com".to_string(),
+            Self::Custom(templates) => templates.closed.clone()
+        }
+    }
+
+    /// The name the site uses for the region of `info`, or `None` if the site doesn't cover it.
+    pub fn region_slug(&self, info: &BasicInfo) -> Option<String> {
+        match (self, info.region) {
+            (Self::Custom(_), _) | (_, None) => Some(info.server.clone()),
+            (_, Some(region)) if !region.is_live() => None,
+            (Self::UGg, Some(region)) => Some(region.platform_id().to_lowercase()),
+            (_, Some(region)) => Some(region.slug())
+        }
+    }
+
+    /// The profile page of the logged in summoner.
+    pub fn profile_url(&self, info: &BasicInfo) -> String {
+        let name = dashed(&info.username);
+        let region = match self.region_slug(info) {
+            Some(region) => region,
+            None => return self.home_url()
+        };
+        match self {
+            Self::OpGg => build_url("https://op.gg/summoners", &[region.as_str(), name.as_str()], &[]),
+            Self::UGg => build_url("https://u.gg/lol/profile", &[region.as_str(), name.to_lowercase().as_str(), "overview"], &[]),
+            Self::Porofessor => build_url("https://porofessor.gg/live", &[region.as_str(), name.as_str()], &[]),
+            Self::LeagueOfGraphs => build_url("https://www.leagueofgraphs.com/summoner", &[region.as_str(), name.as_str()], &[]),
+            Self::Custom(templates) => substitute(&templates.idle, &player_values(info))
+        }
+    }
+
+    /// A page comparing all `players`, usually the own team during champ select.
+    ///
+    /// League of Graphs has no such page, so its profile page is used instead.
+    pub fn multisearch_url(&self, info: &BasicInfo, players: &[Summoner]) -> String {
+        let riot_ids = players
+            .iter()
+            .map(Summoner::riot_id)
+            .collect::<Vec<_>>()
+            .join(",");
+        let region = match self.region_slug(info) {
+            Some(region) => region,
+            None => return self.home_url()
+        };
+        match self {
+            Self::OpGg => build_url("https://op.gg/multisearch", &[region.as_str()], &[("summoners", riot_ids.as_str())]),
+            Self::UGg => build_url("https://u.gg/multisearch", &[], &[("summoners", riot_ids.as_str()), ("region", region.as_str())]),
+            Self::Porofessor => build_url("https://porofessor.gg/pregame", &[region.as_str(), dashed(&riot_ids).as_str()], &[]),
+            Self::LeagueOfGraphs => self.profile_url(info),
+            Self::Custom(templates) => {
+                let mut values = player_values(info);
+                values.push(("names_csv", players
+                    .iter()
+                    .map(|player| encode(&player.riot_id()))
+                    .collect::<Vec<_>>()
+                    .join(",")));
+                substitute(&templates.champ_select, &values)
+            }
+        }
+    }
+
+    /// The live game page of the logged in summoner.
+    ///
+    /// League of Graphs has no such page, so its profile page is used instead.
+    pub fn live_game_url(&self, info: &BasicInfo) -> String {
+        let name = dashed(&info.username);
+        let region = match self.region_slug(info) {
+            Some(region) => region,
+            None => return self.home_url()
+        };
+        match self {
+            Self::OpGg => build_url("https://op.gg/summoners", &[region.as_str(), name.as_str(), "ingame"], &[]),
+            Self::UGg => build_url("https://u.gg/lol/profile", &[region.as_str(), name.to_lowercase().as_str(), "live-game"], &[]),
+            Self::Porofessor => build_url("https://porofessor.gg/live", &[region.as_str(), name.as_str()], &[]),
+            Self::LeagueOfGraphs => self.profile_url(info),
+            Self::Custom(templates) => substitute(&templates.in_game, &player_values(info))
+        }
+    }
+
+    /// The page that fits the current state of the client best.
+    pub fn url_for(&self, status: &ClientStatus) -> String {
+        match (status.state, &status.info) {
+            (ClientState::Idle, Some(info)) => self.profile_url(info),
+            (ClientState::ChampSelect, Some(info)) => self.multisearch_url(info, status.players.as_deref().unwrap_or_default()),
+            (ClientState::InGame, Some(info)) => self.live_game_url(info),
+            _ => self.home_url()
+        }
+    }
+
+}
+
+/// Riot IDs are written as `Name-TAG` in urls.
+fn dashed(riot_id: &str) -> String {
+    riot_id.replace('#', "-")
+}
+
+fn build_url(base: &str, segments: &[&str], query: &[(&str, &str)]) -> String {
+    let mut url = Url::parse(base).expect("base urls are valid");
+    url.path_segments_mut()
+        .expect("base urls are not cannot-be-a-base urls")
+        .pop_if_empty()
+        .extend(segments);
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    url.into()
+}
+
+/// User defined urls for every [`ClientState`].
+///
+/// The templates can contain the following placeholders, which are replaced by url encoded values:
+///
+/// * `{region}`: the region of the client, e.g. `euw`
+/// * `{platform}`: the platform id of the region, e.g. `EUW1`
+/// * `{routing}`: the regional routing value of the region, e.g. `europe`
+/// * `{name}`: the game name of the logged in summoner, or its summoner name for accounts without Riot ID
+/// * `{tag}`: the tag line of the logged in summoner
+/// * `{riot_id}`: the full Riot ID (`name#tag`) of the logged in summoner
+/// * `{puuid}`: the puuid of the logged in summoner
+/// * `{names_csv}`: the comma separated Riot IDs of the own team, only available in `champ_select`
+///
+/// `closed` can't contain any placeholders, as nothing is known about the player while the client is closed.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UrlTemplates {
+    #[serde(default = "UrlTemplates::default_name")]
+    pub name: String,
+    pub closed: String,
+    pub idle: String,
+    pub champ_select: String,
+    pub in_game: String
+}
+
+const PLAYER_PLACEHOLDERS: &[&str] = &["region", "platform", "routing", "name", "tag", "riot_id", "puuid"];
+const TEAM_PLACEHOLDERS: &[&str] = &["region", "platform", "routing", "name", "tag", "riot_id", "puuid", "names_csv"];
+const ALL_PLACEHOLDERS: &[&str] = TEAM_PLACEHOLDERS;
+
+impl UrlTemplates {
+
+    fn default_name() -> String {
+        "Custom".to_string()
+    }
+
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        validate_template("closed", &self.closed, &[])?;
+        validate_template("idle", &self.idle, PLAYER_PLACEHOLDERS)?;
+        validate_template("champ_select", &self.champ_select, TEAM_PLACEHOLDERS)?;
+        validate_template("in_game", &self.in_game, PLAYER_PLACEHOLDERS)?;
+        Ok(())
+    }
+
+}
+
+/// The reasons a custom url template can be rejected.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TemplateError {
+    UnclosedPlaceholder {
+        template: &'static str
+    },
+    UnknownPlaceholder {
+        template: &'static str,
+        placeholder: String
+    },
+    /// The placeholder exists, but has no value in the state the template is used for.
+    UnavailablePlaceholder {
+        template: &'static str,
+        placeholder: String
+    },
+    InvalidUrl {
+        template: &'static str,
+        url: String
+    }
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::UnclosedPlaceholder { template } =>
+                write!(f, "the {} template contains a '{{' without a matching '}}'", template),
+            TemplateError::UnknownPlaceholder { template, placeholder } =>
+                write!(f, "the {} template contains the unknown placeholder {{{}}}. Known placeholders are: {}",
+                       template, placeholder, ALL_PLACEHOLDERS.join(", ")),
+            TemplateError::UnavailablePlaceholder { template, placeholder } =>
+                write!(f, "the placeholder {{{}}} can't be used in the {} template", placeholder, template),
+            TemplateError::InvalidUrl { template, url } =>
+                write!(f, "the {} template doesn't produce a valid http(s) url: {}", template, url)
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Splits `template` into its literal text and placeholder names. Returns `None` for unclosed placeholders.
+fn parse_template(template: &str) -> Option<Vec<(&str, bool)>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')? + start;
+        parts.push((&rest[..start], false));
+        parts.push((&rest[start + 1..end], true));
+        rest = &rest[end + 1..];
+    }
+    parts.push((rest, false));
+    Some(parts)
+}
+
+fn validate_template(name: &'static str, template: &str, available: &[&str]) -> Result<(), TemplateError> {
+    let parts = parse_template(template)
+        .ok_or(TemplateError::UnclosedPlaceholder { template: name })?;
+    for (placeholder, _) in parts.iter().filter(|(_, is_placeholder)| *is_placeholder) {
+        if !ALL_PLACEHOLDERS.contains(placeholder) {
+            return Err(TemplateError::UnknownPlaceholder { template: name, placeholder: placeholder.to_string() });
+        }
+        if !available.contains(placeholder) {
+            return Err(TemplateError::UnavailablePlaceholder { template: name, placeholder: placeholder.to_string() });
+        }
+    }
+    let example = substitute(template, &available
+        .iter()
+        .map(|placeholder| (*placeholder, "example".to_string()))
+        .collect::<Vec<_>>());
+    match Url::parse(&example) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        _ => Err(TemplateError::InvalidUrl { template: name, url: template.to_string() })
+    }
+}
+
+/// Replaces the placeholders in `template` with the given already encoded values.
+fn substitute(template: &str, values: &[(&str, String)]) -> String {
+    match parse_template(template) {
+        Some(parts) => parts
+            .into_iter()
+            .map(|(part, is_placeholder)| match is_placeholder {
+                true => values
+                    .iter()
+                    .find(|(name, _)| *name == part)
+                    .map_or("", |(_, value)| value.as_str()),
+                false => part
+            })
+            .collect(),
+        None => template.to_string()
+    }
+}
+
+fn player_values(info: &BasicInfo) -> Vec<(&'static str, String)> {
+    let name = match info.game_name.is_empty() {
+        true => &info.username,
+        false => &info.game_name
+    };
+    vec![
+        ("region", encode(&info.server)),
+        ("platform", info.region.map_or_else(String::new, |region| region.platform_id().to_string())),
+        ("routing", info.region.map_or_else(String::new, |region| region.routing().to_string())),
+        ("name", encode(name)),
+        ("tag", encode(&info.tag_line)),
+        ("riot_id", encode(&info.username)),
+        ("puuid", encode(&info.puuid))
+    ]
+}
+
+/// Percent-encodes everything except the unreserved characters of RFC 3986.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::region::Region;
+    use super::*;
+
+    fn info() -> BasicInfo {
+        BasicInfo {
+            server: "euw".to_string(),
+            region: Some(Region::Euw),
+            username: "Some Name#EUW".to_string(),
+            game_name: "Some Name".to_string(),
+            tag_line: "EUW".to_string(),
+            puuid: "puuid-1".to_string()
+        }
+    }
+
+    #[test]
+    fn parses_templates() {
+        assert_eq!(parse_template("https://a.b/{region}/x"), Some(vec![
+            ("https://a.b/", false), ("region", true), ("/x", false)
+        ]));
+        assert_eq!(parse_template("https://a.b/x"), Some(vec![("https://a.b/x", false)]));
+        assert_eq!(parse_template("https://a.b/{region/x"), None);
+    }
+
+    #[test]
+    fn rejects_unclosed_placeholders() {
+        assert_eq!(validate_template("idle", "https://a.b/{name", PLAYER_PLACEHOLDERS),
+                   Err(TemplateError::UnclosedPlaceholder { template: "idle" }));
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        assert_eq!(validate_template("idle", "https://a.b/{summoner}", PLAYER_PLACEHOLDERS),
+                   Err(TemplateError::UnknownPlaceholder { template: "idle", placeholder: "summoner".to_string() }));
+    }
+
+    #[test]
+    fn rejects_unavailable_placeholders() {
+        assert_eq!(validate_template("idle", "https://a.b/{names_csv}", PLAYER_PLACEHOLDERS),
+                   Err(TemplateError::UnavailablePlaceholder { template: "idle", placeholder: "names_csv".to_string() }));
+        assert_eq!(validate_template("closed", "https://a.b/{region}", &[]),
+                   Err(TemplateError::UnavailablePlaceholder { template: "closed", placeholder: "region".to_string() }));
+        assert_eq!(validate_template("champ_select", "https://a.b/{region}?names={names_csv}", TEAM_PLACEHOLDERS), Ok(()));
+    }
+
+    #[test]
+    fn rejects_non_http_urls() {
+        assert_eq!(validate_template("idle", "javascript:alert('{name}')", PLAYER_PLACEHOLDERS),
+                   Err(TemplateError::InvalidUrl { template: "idle", url: "javascript:alert('{name}')".to_string() }));
+        assert_eq!(validate_template("idle", "{region}.example.com", PLAYER_PLACEHOLDERS),
+                   Err(TemplateError::InvalidUrl { template: "idle", url: "{region}.example.com".to_string() }));
+        assert_eq!(validate_template("idle", "http://{region}.example.com/{name}", PLAYER_PLACEHOLDERS), Ok(()));
+    }
+
+    #[test]
+    fn substitutes_encoded_values() {
+        let url = substitute("https://a.b/{region}/{riot_id}?tag={tag}&unknown={foo}", &player_values(&info()));
+        assert_eq!(url, "https://a.b/euw/Some%20Name%23EUW?tag=EUW&unknown=");
+    }
+}
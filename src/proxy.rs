@@ -0,0 +1,65 @@
+use std::net::SocketAddr;
+use tide::{Request, Response, StatusCode};
+use tiny_champ_select_helper::connection::SharedClient;
+use crate::AppState;
+use crate::config::ProxyConfig;
+
+/// Forwards requests under `/lcu` to the connected client, so local tools don't have to deal with the
+/// lockfile or Riot's certificate.
+///
+/// Only same-origin requests from loopback addresses that match one of the configured rules are forwarded.
+pub struct LcuProxy {
+    config: ProxyConfig,
+    client: SharedClient
+}
+
+impl LcuProxy {
+
+    pub fn new(config: ProxyConfig, client: SharedClient) -> Self {
+        Self {
+            config,
+            client
+        }
+    }
+
+    /// Whether the rules allow `method` on the LCU path `path`.
+    pub fn is_allowed(&self, method: &str, path: &str) -> bool {
+        !path.split('/').any(|segment| segment == ".." || segment == ".") &&
+            self.config.allow.iter().any(|rule| rule.matches(method, path))
+    }
+
+    pub async fn forward(&self, mut req: Request<AppState>) -> tide::Result<Response> {
+        let loopback = req
+            .peer_addr()
+            .and_then(|addr| addr.parse::<SocketAddr>().ok())
+            .map_or(false, |addr| addr.ip().is_loopback());
+        if !loopback || !req.state().is_same_origin(&req) {
+            log::info!("Rejected proxy request from a foreign origin: {:?}", req.header("Origin"));
+            return Ok(Response::new(StatusCode::Forbidden))
+        }
+
+        let path = format!("/{}", req.param("path")?);
+        if !self.is_allowed(req.method().as_ref(), &path) {
+            log::info!("Rejected proxy request: {} {}", req.method(), path);
+            return Ok(Response::new(StatusCode::Forbidden))
+        }
+
+        let client = match self.client.read().await.clone() {
+            Some(client) => client,
+            None => return Ok(Response::new(StatusCode::ServiceUnavailable))
+        };
+        let uri = match req.url().query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path
+        };
+        let mut request = client
+            .request(req.method(), uri)
+            .body(req.take_body());
+        if let Some(content_type) = req.content_type() {
+            request = request.content_type(content_type);
+        }
+        let response: tide::http::Response = client.send(request).await?.into();
+        Ok(response.into())
+    }
+
+}
@@ -0,0 +1,267 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use schemars::gen::SchemaGenerator;
+use schemars::JsonSchema;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use serde::{Serialize, Serializer};
+
+/// A League of Legends shard.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Region {
+    Br,
+    Eune,
+    Euw,
+    Jp,
+    Kr,
+    Lan,
+    Las,
+    Me,
+    Na,
+    Oce,
+    Ph,
+    Ru,
+    Sg,
+    Th,
+    Tr,
+    Tw,
+    Vn,
+    Pbe
+}
+
+/// The regional routing values of the Riot API.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Routing {
+    Americas,
+    Asia,
+    Europe,
+    Sea
+}
+
+impl Region {
+
+    pub const ALL: [Region; 18] = [
+        Self::Br, Self::Eune, Self::Euw, Self::Jp, Self::Kr, Self::Lan, Self::Las, Self::Me, Self::Na,
+        Self::Oce, Self::Ph, Self::Ru, Self::Sg, Self::Th, Self::Tr, Self::Tw, Self::Vn, Self::Pbe
+    ];
+
+    /// The name of the region as shown to players, e.g. `EUW`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Br => "BR",
+            Self::Eune => "EUNE",
+            Self::Euw => "EUW",
+            Self::Jp => "JP",
+            Self::Kr => "KR",
+            Self::Lan => "LAN",
+            Self::Las => "LAS",
+            Self::Me => "ME",
+            Self::Na => "NA",
+            Self::Oce => "OCE",
+            Self::Ph => "PH",
+            Self::Ru => "RU",
+            Self::Sg => "SG",
+            Self::Th => "TH",
+            Self::Tr => "TR",
+            Self::Tw => "TW",
+            Self::Vn => "VN",
+            Self::Pbe => "PBE"
+        }
+    }
+
+    /// The platform id used by the Riot API, e.g. `EUW1`.
+    pub fn platform_id(&self) -> &'static str {
+        match self {
+            Self::Br => "BR1",
+            Self::Eune => "EUN1",
+            Self::Euw => "EUW1",
+            Self::Jp => "JP1",
+            Self::Kr => "KR",
+            Self::Lan => "LA1",
+            Self::Las => "LA2",
+            Self::Me => "ME1",
+            Self::Na => "NA1",
+            Self::Oce => "OC1",
+            Self::Ph => "PH2",
+            Self::Ru => "RU",
+            Self::Sg => "SG2",
+            Self::Th => "TH2",
+            Self::Tr => "TR1",
+            Self::Tw => "TW2",
+            Self::Vn => "VN2",
+            Self::Pbe => "PBE1"
+        }
+    }
+
+    pub fn routing(&self) -> Routing {
+        match self {
+            Self::Br | Self::Lan | Self::Las | Self::Na | Self::Pbe => Routing::Americas,
+            Self::Jp | Self::Kr => Routing::Asia,
+            Self::Eune | Self::Euw | Self::Me | Self::Ru | Self::Tr => Routing::Europe,
+            Self::Oce | Self::Ph | Self::Sg | Self::Th | Self::Tw | Self::Vn => Routing::Sea
+        }
+    }
+
+    /// The lowercase region name used in the urls of most stats sites, e.g. `euw`.
+    pub fn slug(&self) -> String {
+        self.name().to_lowercase()
+    }
+
+    /// Checks if the region is a regular live server, which is the only kind stats sites track.
+    pub fn is_live(&self) -> bool {
+        !matches!(self, Self::Pbe)
+    }
+
+}
+
+/// The error returned when parsing a region the helper doesn't know yet.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RegionError(pub String);
+
+impl Display for RegionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown region: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for RegionError {}
+
+impl FromStr for Region {
+    type Err = RegionError;
+
+    /// Accepts both region names (`OCE`) and platform ids (`OC1`), as the client uses either depending on the shard.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.to_uppercase();
+        Self::ALL
+            .into_iter()
+            .find(|region| region.name() == upper || region.platform_id() == upper)
+            .ok_or_else(|| RegionError(s.to_string()))
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Region {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl JsonSchema for Region {
+    fn schema_name() -> String {
+        "Region".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(Self::ALL.iter().map(|region| region.name().into()).collect()),
+            ..Default::default()
+        }.into()
+    }
+}
+
+impl Display for Routing {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Americas => "americas",
+            Self::Asia => "asia",
+            Self::Europe => "europe",
+            Self::Sea => "sea"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client_state::BasicInfo;
+    use crate::providers::StatsProvider;
+
+    /// Region, platform id, routing, and the region in the urls of op.gg, u.gg, Porofessor and League of Graphs.
+    /// `None` means the site doesn't cover the region.
+    const SHARDS: [(Region, &str, Routing, Option<[&str; 4]>); 18] = [
+        (Region::Br, "BR1", Routing::Americas, Some(["br", "br1", "br", "br"])),
+        (Region::Eune, "EUN1", Routing::Europe, Some(["eune", "eun1", "eune", "eune"])),
+        (Region::Euw, "EUW1", Routing::Europe, Some(["euw", "euw1", "euw", "euw"])),
+        (Region::Jp, "JP1", Routing::Asia, Some(["jp", "jp1", "jp", "jp"])),
+        (Region::Kr, "KR", Routing::Asia, Some(["kr", "kr", "kr", "kr"])),
+        (Region::Lan, "LA1", Routing::Americas, Some(["lan", "la1", "lan", "lan"])),
+        (Region::Las, "LA2", Routing::Americas, Some(["las", "la2", "las", "las"])),
+        (Region::Me, "ME1", Routing::Europe, Some(["me", "me1", "me", "me"])),
+        (Region::Na, "NA1", Routing::Americas, Some(["na", "na1", "na", "na"])),
+        (Region::Oce, "OC1", Routing::Sea, Some(["oce", "oc1", "oce", "oce"])),
+        (Region::Ph, "PH2", Routing::Sea, Some(["ph", "ph2", "ph", "ph"])),
+        (Region::Ru, "RU", Routing::Europe, Some(["ru", "ru", "ru", "ru"])),
+        (Region::Sg, "SG2", Routing::Sea, Some(["sg", "sg2", "sg", "sg"])),
+        (Region::Th, "TH2", Routing::Sea, Some(["th", "th2", "th", "th"])),
+        (Region::Tr, "TR1", Routing::Europe, Some(["tr", "tr1", "tr", "tr"])),
+        (Region::Tw, "TW2", Routing::Sea, Some(["tw", "tw2", "tw", "tw"])),
+        (Region::Vn, "VN2", Routing::Sea, Some(["vn", "vn2", "vn", "vn"])),
+        (Region::Pbe, "PBE1", Routing::Americas, None)
+    ];
+
+    fn info(region: Region) -> BasicInfo {
+        BasicInfo {
+            server: region.slug(),
+            region: Some(region),
+            username: "Name#TAG".to_string(),
+            game_name: "Name".to_string(),
+            tag_line: "TAG".to_string(),
+            puuid: "puuid".to_string()
+        }
+    }
+
+    #[test]
+    fn covers_every_region() {
+        for region in Region::ALL {
+            assert!(SHARDS.iter().any(|(shard, ..)| *shard == region), "{} is missing", region);
+        }
+    }
+
+    #[test]
+    fn parses_names_and_platform_ids() {
+        for (region, platform_id, ..) in SHARDS {
+            assert_eq!(region.platform_id(), platform_id);
+            assert_eq!(platform_id.parse(), Ok(region));
+            assert_eq!(platform_id.to_lowercase().parse(), Ok(region));
+            assert_eq!(region.name().parse(), Ok(region));
+            assert_eq!(region.slug().parse(), Ok(region));
+            assert_eq!(region.to_string(), region.name());
+        }
+        assert_eq!("EUW2".parse::<Region>(), Err(RegionError("EUW2".to_string())));
+        assert_eq!("".parse::<Region>(), Err(RegionError(String::new())));
+    }
+
+    #[test]
+    fn maps_routing_and_slugs() {
+        for (region, _, routing, sites) in SHARDS {
+            assert_eq!(region.routing(), routing, "{}", region);
+            assert_eq!(region.slug(), region.name().to_lowercase());
+            assert_eq!(region.is_live(), sites.is_some(), "{}", region);
+        }
+    }
+
+    #[test]
+    fn builds_provider_urls() {
+        for (region, _, _, sites) in SHARDS {
+            let info = info(region);
+            let expected = match sites {
+                Some([op_gg, u_gg, porofessor, league_of_graphs]) => [
+                    format!("https://op.gg/summoners/{}/Name-TAG", op_gg),
+                    format!("https://u.gg/lol/profile/{}/name-tag/overview", u_gg),
+                    format!("https://porofessor.gg/live/{}/Name-TAG", porofessor),
+                    format!("https://www.leagueofgraphs.com/summoner/{}/Name-TAG", league_of_graphs)
+                ],
+                None => StatsProvider::ALL.map(|provider| provider.home_url())
+            };
+            for (provider, expected) in StatsProvider::ALL.iter().zip(expected) {
+                assert_eq!(provider.profile_url(&info), expected, "{} on {}", region, provider.name());
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_broadcast::{InactiveReceiver, Receiver};
+use async_std::channel::Sender;
+use async_std::sync::Mutex;
+use crate::lcu::LcuEvent;
+
+/// A request from an [`EventRelay`] to the connection manager.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RelayCommand {
+    Subscribe(String),
+    Unsubscribe(String),
+    /// Reloads the whole status from the client.
+    Refresh
+}
+
+/// Shares the event websocket of the [`ConnectionManager`](crate::connection::ConnectionManager) between
+/// any number of consumers.
+///
+/// Subscriptions are reference counted: the manager subscribes to an endpoint when the first consumer
+/// asks for it and unsubscribes once the last one is done with it.
+#[derive(Clone)]
+pub struct EventRelay {
+    commands: Sender<RelayCommand>,
+    events: InactiveReceiver<LcuEvent>,
+    counts: Arc<Mutex<HashMap<String, usize>>>
+}
+
+impl EventRelay {
+
+    pub fn new(commands: Sender<RelayCommand>, events: InactiveReceiver<LcuEvent>) -> Self {
+        Self {
+            commands,
+            events,
+            counts: Default::default()
+        }
+    }
+
+    /// Returns a receiver for every event of the websocket. Consumers have to filter out the endpoints
+    /// they are interested in themselves.
+    pub fn events(&self) -> Receiver<LcuEvent> {
+        self.events.activate_cloned()
+    }
+
+    /// Registers interest in the events of `endpoint`.
+    pub async fn subscribe(&self, endpoint: &str) {
+        let mut counts = self.counts.lock().await;
+        let count = counts.entry(endpoint.to_string()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            self.commands.send(RelayCommand::Subscribe(endpoint.to_string())).await.ok();
+        }
+    }
+
+    /// Cancels a previous [`subscribe`](Self::subscribe) call for `endpoint`.
+    pub async fn unsubscribe(&self, endpoint: &str) {
+        let mut counts = self.counts.lock().await;
+        if let Some(count) = counts.get_mut(endpoint) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(endpoint);
+                self.commands.send(RelayCommand::Unsubscribe(endpoint.to_string())).await.ok();
+            }
+        }
+    }
+
+    /// Asks the manager to reload the status from the client, as soon as one is connected.
+    pub async fn refresh(&self) {
+        self.commands.send(RelayCommand::Refresh).await.ok();
+    }
+
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use async_std::prelude::FutureExt;
+use futures::{FutureExt as _, StreamExt};
+use tide::{Endpoint, Request, StatusCode};
+use tide_websockets::{Message, WebSocket, WebSocketConnection};
+use tiny_champ_select_helper::client_state::ClientStatus;
+use tiny_champ_select_helper::lcu::LcuEvent;
+use crate::AppState;
+use tiny_champ_select_helper::providers::StatsProvider;
+use crate::protocol::{ClientMessage, Command, MessageId, Response, ServerMessage, StatusMessage};
+
+enum Input {
+    Message(Option<Result<Message, tide_websockets::Error>>),
+    Status(Option<ClientStatus>),
+    Event(Option<LcuEvent>)
+}
+
+struct Session<'a> {
+    state: &'a AppState,
+    stream: WebSocketConnection,
+    subscriptions: HashSet<String>
+}
+
+impl<'a> Session<'a> {
+
+    /// Subscriptions match by prefix, so events are checked against the proxy rules again.
+    fn is_allowed(&self, event: &LcuEvent) -> bool {
+        self.state.proxy
+            .as_ref()
+            .map_or(false, |proxy| proxy.is_allowed("GET", &event.uri))
+    }
+
+    async fn send_status(&mut self, status: &ClientStatus) -> tide::Result<()> {
+        let provider = self.state.provider().await;
+        self.send(&ServerMessage::Status(StatusMessage::new(status, &provider))).await
+    }
+
+    async fn send(&mut self, message: &ServerMessage<'_>) -> tide::Result<()> {
+        Ok(self.stream.send_string(message.to_json()?).await?)
+    }
+
+    async fn execute(&mut self, command: Command) -> tide::Result<Response> {
+        Ok(match command {
+            Command::Ping => Response::Pong,
+            Command::Refresh => {
+                self.state.relay.refresh().await;
+                Response::Ok
+            },
+            Command::OpenProvider => {
+                let url = self.state.url().await;
+                match webbrowser::open(&url) {
+                    Ok(_) => Response::Url { url },
+                    Err(err) => Response::Error { message: err.to_string() }
+                }
+            },
+            Command::SetProvider { provider } => match provider.validate() {
+                Ok(()) if matches!(provider, StatsProvider::Custom(_)) && provider != self.state.configured_provider => {
+                    Response::Error { message: "Custom stats sites can only be set in the config".to_string() }
+                },
+                Ok(()) => {
+                    log::info!("Switching stats site to {}", provider.name());
+                    self.state.set_provider(provider.clone()).await;
+                    let status = self.state.status.latest().await;
+                    self.send_status(&status).await?;
+                    Response::Provider {
+                        name: provider.name().to_string(),
+                        provider
+                    }
+                },
+                Err(err) => Response::Error { message: err.to_string() }
+            },
+            Command::Subscribe { endpoint } => match &self.state.proxy {
+                Some(proxy) if proxy.is_allowed("GET", &endpoint) => {
+                    if self.subscriptions.insert(endpoint.clone()) {
+                        self.state.relay.subscribe(&endpoint).await;
+                    }
+                    Response::Ok
+                },
+                Some(_) => Response::Error { message: format!("{} is not allowed by the lcu_proxy rules", endpoint) },
+                None => Response::Error { message: "Subscriptions require lcu_proxy in the config".to_string() }
+            },
+            Command::Unsubscribe { endpoint } => {
+                if self.subscriptions.remove(&endpoint) {
+                    self.state.relay.unsubscribe(&endpoint).await;
+                }
+                Response::Ok
+            }
+        })
+    }
+
+    async fn handle_message(&mut self, text: &str) -> tide::Result<()> {
+        let (id, response) = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage { id, command }) => (id, self.execute(command).await?),
+            Err(err) => {
+                log::info!("Got invalid message: {}", err);
+                let id = serde_json::from_str::<MessageId>(text).ok().and_then(|message| message.id);
+                (id, Response::Error { message: err.to_string() })
+            }
+        };
+        self.send(&ServerMessage::Response { id, response }).await
+    }
+
+    async fn run(&mut self) -> tide::Result<()> {
+        let (status, mut statuses) = self.state.status.subscribe().await;
+        let mut events = self.state.relay.events();
+        self.send_status(&status).await?;
+        loop {
+            let input = self.stream.next().map(Input::Message)
+                .race(statuses.next().map(Input::Status))
+                .race(events.next().map(Input::Event))
+                .await;
+            match input {
+                Input::Message(msg) => match msg {
+                    Some(Ok(Message::Text(text))) => self.handle_message(&text).await?,
+                    Some(Ok(Message::Close(_))) => {},
+                    Some(msg) => log::info!("Got unexpected message: {:?}", msg),
+                    None => return Ok(())
+                },
+                Input::Status(status) => match status {
+                    Some(status) => self.send_status(&status).await?,
+                    None => return Ok(())
+                },
+                Input::Event(event) => match event {
+                    Some(event) => if self.subscriptions.iter().any(|endpoint| event.matches(endpoint)) && self.is_allowed(&event) {
+                        self.send(&ServerMessage::Event((&event).into())).await?
+                    },
+                    None => return Ok(())
+                }
+            }
+        }
+    }
+
+}
+
+/// Upgrades requests from pages served by the helper to a websocket handled by [`handle`].
+pub async fn upgrade(req: Request<AppState>) -> tide::Result {
+    if !req.state().is_same_origin(&req) {
+        log::info!("Rejected websocket from a foreign origin: {:?}", req.header("Origin"));
+        return Ok(tide::Response::new(StatusCode::Forbidden))
+    }
+    WebSocket::new(handle).call(req).await
+}
+
+/// Pushes every status change to the client, relays the LCU events it subscribed to and executes its commands.
+pub async fn handle(req: Request<AppState>, stream: WebSocketConnection) -> tide::Result<()> {
+    let mut session = Session {
+        state: req.state(),
+        stream,
+        subscriptions: HashSet::new()
+    };
+    let result = session.run().await;
+    for endpoint in &session.subscriptions {
+        session.state.relay.unsubscribe(endpoint).await;
+    }
+    result
+}
@@ -1,20 +1,24 @@
 use async_std::sync::{Mutex, Arc};
 use async_broadcast::{InactiveReceiver, Receiver};
-use async_std::prelude::StreamExt;
+use async_std::prelude::{Stream, StreamExt};
 use async_std::task;
 use async_std::task::JoinHandle;
 
+/// Keeps track of the latest value sent through a broadcast channel so that new subscribers
+/// can start with the current state instead of waiting for the next change.
+///
+/// The sender numbers every value, starting with `1`. The default value has the number `0`.
 #[derive(Clone)]
 pub struct ReceiveWrapper<T> {
-    receiver: InactiveReceiver<T>,
-    last_value: Arc<Mutex<T>>,
+    receiver: InactiveReceiver<(u64, T)>,
+    last_value: Arc<Mutex<(u64, T)>>,
     _handle: Arc<JoinHandle<()>>
 }
 
 impl <T> ReceiveWrapper<T>
     where T: Default + Clone + Send + Sync + 'static
 {
-    pub fn new(receiver: Receiver<T>) -> Self {
+    pub fn new(receiver: Receiver<(u64, T)>) -> Self {
         Self::new_with_default(receiver, T::default())
     }
 }
@@ -23,15 +27,14 @@ impl<T> ReceiveWrapper<T>
     where T: Clone + Send + Sync + 'static
 {
 
-    pub fn new_with_default(receiver: Receiver<T>, default_value: impl Into<T>) -> Self {
-        let last_value = Arc::new(Mutex::new(default_value.into()));
+    pub fn new_with_default(receiver: Receiver<(u64, T)>, default_value: impl Into<T>) -> Self {
+        let last_value = Arc::new(Mutex::new((0, default_value.into())));
         let _handle = Arc::new({
             let last_value = last_value.clone();
             let mut receiver = receiver.clone();
             task::spawn(async move {
                 while let Some(val) = receiver.next().await {
-                    let mut x = last_value.lock_arc().await;
-                    *x = val;
+                    *last_value.lock_arc().await = val;
                 }
             })
         });
@@ -42,10 +45,23 @@ impl<T> ReceiveWrapper<T>
         }
     }
 
-    pub async fn subscribe(&self) -> (T, Receiver<T>) {
+    /// Returns the latest value.
+    pub async fn latest(&self) -> T {
+        self.last_value.lock_arc().await.1.clone()
+    }
+
+    /// Returns the latest value together with a stream of all following values.
+    pub async fn subscribe(&self) -> (T, impl Stream<Item = T> + Unpin + Send) {
+        let (_, value, receiver) = self.subscribe_numbered().await;
+        (value, receiver.map(|(_, value)| value))
+    }
+
+    /// Like [`subscribe`](Self::subscribe), but keeps the numbers the sender assigned to the values.
+    pub async fn subscribe_numbered(&self) -> (u64, T, impl Stream<Item = (u64, T)> + Unpin + Send) {
         let receiver = self.receiver.activate_cloned();
-        let value = self.last_value.lock_arc().await.clone();
-        (value, receiver)
+        let (id, value) = self.last_value.lock_arc().await.clone();
+        // The receiver can still hold values that arrived before `last_value` was updated.
+        (id, value, receiver.filter(move |(n, _)| *n > id))
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,112 @@
+mod mock_lcu;
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use anyhow::{bail, Result};
+use async_broadcast::Receiver;
+use async_native_tls::Certificate;
+use async_std::task;
+use async_std::task::JoinHandle;
+use tiny_champ_select_helper::automation::AutoAccept;
+use tiny_champ_select_helper::client_state::ClientStatus;
+use tiny_champ_select_helper::connection::ConnectionManager;
+use tiny_champ_select_helper::lcu::endpoints;
+use crate::mock_lcu::MockLcu;
+
+const DELAY: Duration = Duration::from_millis(500);
+
+/// The helper's connection manager and auto accept, connected to a mock client.
+struct Setup {
+    mock: MockLcu,
+    client_dir: PathBuf,
+    _statuses: Receiver<(u64, ClientStatus)>,
+    manager: JoinHandle<()>,
+    auto_accept: JoinHandle<()>
+}
+
+impl Setup {
+
+    async fn start(name: &str, enabled: bool) -> Result<Self> {
+        let client_dir = std::env::temp_dir().join(format!("tiny-champ-select-helper-{}-{}", name, std::process::id()));
+        let mock = MockLcu::start(&client_dir, 0).await?;
+
+        let (sender, _statuses) = async_broadcast::broadcast(16);
+        let manager = ConnectionManager::new(Some(client_dir.join("lockfile")), sender)
+            .with_certificate(Some(Certificate::from_pem(mock_lcu::CERTIFICATE)?));
+        let auto_accept = task::spawn(AutoAccept::new(enabled, DELAY).run(manager.relay(), manager.client()));
+        let manager = task::spawn(manager.run());
+
+        let setup = Self {
+            mock,
+            client_dir,
+            _statuses,
+            manager,
+            auto_accept
+        };
+        wait_until(|| async { setup.mock.is_subscribed(endpoints::READY_CHECK).await.then_some(()) }).await?;
+        Ok(setup)
+    }
+
+    /// When the ready check was accepted, if it was.
+    async fn accepted(&self) -> Option<Instant> {
+        self.mock
+            .requests().await
+            .into_iter()
+            .find(|request| request.method == "POST" && request.path == endpoints::READY_CHECK_ACCEPT)
+            .map(|request| request.time)
+    }
+
+    async fn stop(self) -> Result<()> {
+        self.auto_accept.cancel().await;
+        self.manager.cancel().await;
+        self.mock.stop().await?;
+        async_std::fs::remove_dir_all(&self.client_dir).await?;
+        Ok(())
+    }
+
+}
+
+/// Polls `condition` until it returns a value.
+async fn wait_until<T, F: Future<Output = Option<T>>>(mut condition: impl FnMut() -> F) -> Result<T> {
+    let timeout = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < timeout {
+        if let Some(value) = condition().await {
+            return Ok(value)
+        }
+        task::sleep(Duration::from_millis(20)).await;
+    }
+    bail!("timed out")
+}
+
+#[async_std::test]
+async fn accepts_after_delay() -> Result<()> {
+    let setup = Setup::start("accept", true).await?;
+    let started = Instant::now();
+    setup.mock.start_ready_check().await;
+    task::sleep(DELAY / 2).await;
+    assert_eq!(setup.accepted().await, None, "accepted before the delay");
+    let accepted = wait_until(|| setup.accepted()).await?;
+    assert!(accepted.duration_since(started) >= DELAY);
+    setup.stop().await
+}
+
+#[async_std::test]
+async fn ignores_ready_checks_when_disabled() -> Result<()> {
+    let setup = Setup::start("disabled", false).await?;
+    setup.mock.start_ready_check().await;
+    task::sleep(DELAY * 3).await;
+    assert_eq!(setup.accepted().await, None);
+    setup.stop().await
+}
+
+#[async_std::test]
+async fn respects_declines_during_delay() -> Result<()> {
+    let setup = Setup::start("declined", true).await?;
+    setup.mock.start_ready_check().await;
+    task::sleep(DELAY / 4).await;
+    setup.mock.respond_to_ready_check("Declined").await;
+    task::sleep(DELAY * 3).await;
+    assert_eq!(setup.accepted().await, None);
+    setup.stop().await
+}
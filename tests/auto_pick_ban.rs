@@ -0,0 +1,140 @@
+mod mock_lcu;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use anyhow::{bail, Result};
+use async_native_tls::Certificate;
+use async_std::task;
+use async_std::task::JoinHandle;
+use serde_json::{json, Value};
+use tiny_champ_select_helper::automation::{AutoPickBan, ChampionPriorities};
+use tiny_champ_select_helper::connection::ConnectionManager;
+use tiny_champ_select_helper::lcu::endpoints;
+use tiny_champ_select_helper::util::ReceiveWrapper;
+use crate::mock_lcu::MockLcu;
+
+const DELAY: Duration = Duration::from_millis(300);
+const PICKS: [i64; 3] = [103, 84, 61];
+const CHAMPIONS: [i64; 5] = [1, 103, 84, 61, 99];
+
+/// The helper's connection manager and champ select automation, connected to a mock client in champ select.
+struct Setup {
+    mock: MockLcu,
+    client_dir: PathBuf,
+    manager: JoinHandle<()>,
+    auto_pick_ban: JoinHandle<()>
+}
+
+impl Setup {
+
+    async fn start(name: &str, hover_only: bool, session: Value) -> Result<Self> {
+        let client_dir = std::env::temp_dir().join(format!("tiny-champ-select-helper-{}-{}", name, std::process::id()));
+        let mock = MockLcu::start(&client_dir, 0).await?;
+        mock.set(endpoints::PICKABLE_CHAMPIONS, json!(CHAMPIONS)).await;
+        mock.set(endpoints::BANNABLE_CHAMPIONS, json!(CHAMPIONS)).await;
+        mock.set("/lol-summoner/v2/summoners?ids=[]", json!([])).await;
+        mock.set(endpoints::CHAMP_SELECT_SESSION, session).await;
+
+        let (sender, statuses) = async_broadcast::broadcast(16);
+        let manager = ConnectionManager::new(Some(client_dir.join("lockfile")), sender)
+            .with_certificate(Some(Certificate::from_pem(mock_lcu::CERTIFICATE)?));
+        let priorities = ChampionPriorities {
+            picks: HashMap::from([("default".to_string(), PICKS.to_vec())]),
+            bans: HashMap::new()
+        };
+        let auto_pick_ban = task::spawn(AutoPickBan::new(priorities, hover_only, DELAY)
+            .run(ReceiveWrapper::new(statuses), manager.client()));
+        let manager = task::spawn(manager.run());
+
+        let setup = Self {
+            mock,
+            client_dir,
+            manager,
+            auto_pick_ban
+        };
+        wait_until(|| async { setup.mock.is_subscribed(endpoints::GAMEFLOW_PHASE).await.then_some(()) }).await?;
+        setup.mock.set_phase("ChampSelect").await;
+        Ok(setup)
+    }
+
+    /// The changes the helper sent for the pick of the local player.
+    async fn updates(&self) -> Vec<Value> {
+        self.mock
+            .requests().await
+            .into_iter()
+            .filter(|request| request.method == "PATCH" && request.path == format!("{}/1", endpoints::CHAMP_SELECT_ACTIONS))
+            .map(|request| serde_json::from_str(&request.body).unwrap())
+            .collect()
+    }
+
+    async fn stop(self) -> Result<()> {
+        self.auto_pick_ban.cancel().await;
+        self.manager.cancel().await;
+        self.mock.stop().await?;
+        async_std::fs::remove_dir_all(&self.client_dir).await?;
+        Ok(())
+    }
+
+}
+
+/// A draft with the pick of the local player (action `1`) in progress and `hovered` hovered.
+fn session(hovered: i64) -> Value {
+    json!({
+        "localPlayerCellId": 0,
+        "myTeam": [{"cellId": 0, "assignedPosition": "middle"}],
+        "theirTeam": [{"cellId": 5}],
+        "actions": [[
+            {"id": 1, "actorCellId": 0, "championId": hovered, "completed": false, "isInProgress": true, "type": "pick"}
+        ]],
+        "timer": {"phase": "BAN_PICK"}
+    })
+}
+
+/// Polls `condition` until it returns a value.
+async fn wait_until<T, F: Future<Output = Option<T>>>(mut condition: impl FnMut() -> F) -> Result<T> {
+    let timeout = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < timeout {
+        if let Some(value) = condition().await {
+            return Ok(value)
+        }
+        task::sleep(Duration::from_millis(20)).await;
+    }
+    bail!("timed out")
+}
+
+#[async_std::test]
+async fn never_locks_in_hover_only_mode() -> Result<()> {
+    let setup = Setup::start("hover-only", true, session(0)).await?;
+    wait_until(|| async { (!setup.updates().await.is_empty()).then_some(()) }).await?;
+    task::sleep(DELAY * 3).await;
+    assert_eq!(setup.updates().await, vec![json!({"championId": PICKS[0], "completed": false})]);
+    setup.stop().await
+}
+
+#[async_std::test]
+async fn leaves_the_pick_to_the_player_after_a_new_hover() -> Result<()> {
+    let setup = Setup::start("rehover", false, session(0)).await?;
+    wait_until(|| async { (!setup.updates().await.is_empty()).then_some(()) }).await?;
+    setup.mock.set(endpoints::CHAMP_SELECT_SESSION, session(99)).await;
+    task::sleep(DELAY * 3).await;
+    assert_eq!(setup.updates().await, vec![json!({"championId": PICKS[0], "completed": false})]);
+    setup.stop().await
+}
+
+#[async_std::test]
+async fn skips_banned_and_picked_champions() -> Result<()> {
+    let mut draft = session(0);
+    draft["bans"] = json!({"theirTeamBans": [PICKS[0]]});
+    draft["actions"][0].as_array_mut().unwrap().insert(0, json!(
+        {"id": 0, "actorCellId": 5, "championId": PICKS[1], "completed": true, "isInProgress": false, "type": "pick"}
+    ));
+    let setup = Setup::start("unavailable", false, draft).await?;
+    wait_until(|| async { (setup.updates().await.len() == 2).then_some(()) }).await?;
+    assert_eq!(setup.updates().await, vec![
+        json!({"championId": PICKS[2], "completed": false}),
+        json!({"championId": PICKS[2], "completed": true})
+    ]);
+    setup.stop().await
+}
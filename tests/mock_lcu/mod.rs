@@ -0,0 +1,398 @@
+//! A tiny stand-in for the League Client, used by the integration tests and the `mock_lcu` example.
+//!
+//! It writes a `lockfile` into the given directory, serves the REST endpoints the helper needs over
+//! HTTPS and speaks the WAMP-style websocket protocol on the same port. The server uses the self-signed
+//! identity for `127.0.0.1` next to this file, so clients have to trust [`CERTIFICATE`] (`cert.pem`).
+//!
+//! The identity was created with:
+//!
+//! ```text
+//! openssl req -x509 -newkey rsa:2048 -nodes -days 36500 -keyout key.pem -out cert.pem \
+//!     -subj "/CN=127.0.0.1" -addext "subjectAltName=IP:127.0.0.1"
+//! openssl pkcs12 -export -inkey key.pem -in cert.pem -out identity.p12 -passout pass:mock \
+//!     -certpbe PBE-SHA1-3DES -keypbe PBE-SHA1-3DES -macalg sha1
+//! ```
+
+// Every user of the mock only needs some of its functions.
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use anyhow::{Context, Result};
+use async_native_tls::{TlsAcceptor, TlsStream};
+use async_std::channel::{Sender, unbounded};
+use async_std::fs;
+use async_std::io::{BufReader, prelude::BufReadExt};
+use async_std::net::{TcpListener, TcpStream};
+use async_std::sync::Mutex;
+use async_std::task;
+use async_std::task::JoinHandle;
+use async_tungstenite::tungstenite::handshake::derive_accept_key;
+use async_tungstenite::tungstenite::protocol::Role;
+use async_tungstenite::tungstenite::Message;
+use async_tungstenite::WebSocketStream;
+use futures::{AsyncReadExt, AsyncWriteExt, SinkExt, StreamExt};
+use serde_json::{json, Value};
+
+/// The certificate of the mock, to be passed to `ConnectionManager::with_certificate`.
+pub const CERTIFICATE: &[u8] = include_bytes!("cert.pem");
+const IDENTITY: &[u8] = include_bytes!("identity.p12");
+const IDENTITY_PASSWORD: &str = "mock";
+
+const READY_CHECK: &str = "/lol-matchmaking/v1/ready-check";
+const READY_CHECK_ACCEPT: &str = "/lol-matchmaking/v1/ready-check/accept";
+const CHAMP_SELECT_SESSION: &str = "/lol-champ-select/v1/session";
+const CHAMP_SELECT_ACTIONS: &str = "/lol-champ-select/v1/session/actions/";
+
+type Stream = TlsStream<TcpStream>;
+
+#[derive(Default)]
+struct MockState {
+    resources: HashMap<String, Value>,
+    clients: Vec<MockClient>,
+    requests: Vec<MockRequest>
+}
+
+/// A request the mock received, in the order they arrived.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+    pub time: Instant
+}
+
+impl std::fmt::Display for MockRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.method, self.path, self.body)
+    }
+}
+
+struct MockClient {
+    subscriptions: Arc<Mutex<HashSet<String>>>,
+    sender: Sender<String>
+}
+
+impl MockState {
+
+    fn new() -> Self {
+        let mut state = Self::default();
+        state.resources.insert("/riotclient/region-locale".into(), json!({
+            "locale": "en_GB",
+            "region": "EUW",
+            "webLanguage": "en",
+            "webRegion": "euw"
+        }));
+        state.resources.insert("/lol-summoner/v1/current-summoner".into(), json!({
+            "accountId": 1,
+            "displayName": "MockSummoner",
+            "gameName": "MockSummoner",
+            "tagLine": "EUW",
+            "summonerId": 1,
+            "puuid": "00000000-0000-0000-0000-000000000001"
+        }));
+        state.resources.insert("/lol-gameflow/v1/gameflow-phase".into(), json!("None"));
+        state
+    }
+
+    async fn publish(&mut self, uri: &str, event_type: &str, data: Value) {
+        let specific = format!("OnJsonApiEvent{}", uri).replace('/', "_");
+        let mut alive = Vec::new();
+        for client in self.clients.drain(..) {
+            let subscriptions = client.subscriptions.lock().await.clone();
+            let mut ok = true;
+            for name in [specific.as_str(), "OnJsonApiEvent"] {
+                if subscriptions.contains(name) {
+                    let msg = json!([8, name, {"data": data, "eventType": event_type, "uri": uri}]);
+                    ok &= client.sender.send(msg.to_string()).await.is_ok();
+                }
+            }
+            if ok {
+                alive.push(client);
+            }
+        }
+        self.clients = alive;
+    }
+
+    async fn update(&mut self, uri: &str, event_type: &str, value: Value) {
+        match event_type {
+            "Delete" => self.resources.remove(uri),
+            _ => self.resources.insert(uri.to_string(), value.clone())
+        };
+        self.publish(uri, event_type, value).await;
+    }
+
+    async fn respond_to_ready_check(&mut self, response: &str) {
+        if let Some(ready_check) = self.resources.get_mut(READY_CHECK) {
+            ready_check["playerResponse"] = json!(response);
+            let value = ready_check.clone();
+            self.publish(READY_CHECK, "Update", value).await;
+        }
+    }
+
+    /// Applies the changes to an action of the champ select session, like hovering or locking a champion.
+    async fn update_action(&mut self, id: i64, changes: Value) {
+        let session = match self.resources.get_mut(CHAMP_SELECT_SESSION) {
+            Some(session) => session,
+            None => return
+        };
+        let action = session["actions"]
+            .as_array_mut()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_array_mut)
+            .flatten()
+            .find(|action| action["id"] == id);
+        if let (Some(action), Some(changes)) = (action, changes.as_object()) {
+            for (key, value) in changes {
+                action[key] = value.clone();
+            }
+            if action["completed"] == true {
+                action["isInProgress"] = json!(false);
+            }
+            let value = session.clone();
+            self.publish(CHAMP_SELECT_SESSION, "Update", value).await;
+        }
+    }
+
+}
+
+struct Request {
+    method: String,
+    path: String,
+    headers: HashMap<String, String>,
+    body: Vec<u8>
+}
+
+async fn read_request(reader: &mut BufReader<Stream>) -> Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None)
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().context("missing method")?.to_string();
+    let path = parts.next().context("missing path")?.to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .map(|len| len.parse::<usize>())
+        .transpose()?
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+
+    Ok(Some(Request {
+        method,
+        path,
+        headers,
+        body
+    }))
+}
+
+async fn write_response(stream: &mut BufReader<Stream>, status: &str, body: &str) -> Result<()> {
+    let response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                           status, body.len(), body);
+    stream.get_mut().write_all(response.as_bytes()).await?;
+    stream.get_mut().flush().await?;
+    Ok(())
+}
+
+async fn handle_connection(state: Arc<Mutex<MockState>>, stream: Stream) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    while let Some(request) = read_request(&mut reader).await? {
+        log::info!("{} {}", request.method, request.path);
+        state.lock().await.requests.push(MockRequest {
+            method: request.method.clone(),
+            path: request.path.clone(),
+            body: String::from_utf8_lossy(&request.body).to_string(),
+            time: Instant::now()
+        });
+
+        if let Some(key) = request.headers.get("sec-websocket-key") {
+            let accept = derive_accept_key(key.as_bytes());
+            let response = format!("HTTP/1.1 101 Switching Protocols\r\nConnection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Accept: {}\r\n\r\n", accept);
+            let mut stream = reader.into_inner();
+            stream.write_all(response.as_bytes()).await?;
+            stream.flush().await?;
+            return handle_websocket(state, WebSocketStream::from_raw_socket(stream, Role::Server, None).await).await
+        }
+
+        let mut state = state.lock().await;
+        if request.method == "POST" && request.path == READY_CHECK_ACCEPT {
+            state.respond_to_ready_check("Accepted").await;
+        }
+        if let Some(id) = request.path.strip_prefix(CHAMP_SELECT_ACTIONS).and_then(|id| id.parse().ok()) {
+            if request.method == "PATCH" {
+                state.update_action(id, serde_json::from_slice(&request.body)?).await;
+            }
+        }
+        let resource = state.resources.get(&request.path).cloned();
+        drop(state);
+        match (request.method.as_str(), resource) {
+            ("GET", Some(value)) => write_response(&mut reader, "200 OK", &value.to_string()).await?,
+            ("GET", None) => write_response(&mut reader, "404 Not Found",
+                                            &json!({"errorCode": "RPC_ERROR", "httpStatus": 404, "message": "mock resource not found"}).to_string()).await?,
+            _ => write_response(&mut reader, "204 No Content", "").await?
+        }
+    }
+    Ok(())
+}
+
+async fn handle_websocket(state: Arc<Mutex<MockState>>, socket: WebSocketStream<Stream>) -> Result<()> {
+    let (mut sink, mut stream) = socket.split();
+    let (sender, receiver) = unbounded::<String>();
+    let subscriptions = Arc::new(Mutex::new(HashSet::new()));
+    state.lock().await.clients.push(MockClient {
+        subscriptions: subscriptions.clone(),
+        sender
+    });
+
+    let writer = task::spawn(async move {
+        let mut receiver = receiver;
+        while let Some(msg) = receiver.next().await {
+            if sink.send(Message::Text(msg)).await.is_err() {
+                break
+            }
+        }
+    });
+
+    while let Some(msg) = stream.next().await {
+        match msg? {
+            Message::Text(text) => match serde_json::from_str::<(u32, String)>(&text) {
+                Ok((5, name)) => { subscriptions.lock().await.insert(name); },
+                Ok((6, name)) => { subscriptions.lock().await.remove(&name); },
+                _ => log::warn!("Unsupported websocket message: {}", text)
+            },
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    writer.cancel().await;
+    Ok(())
+}
+
+/// A running mock client. Call [`stop`](Self::stop) to remove its lockfile again.
+pub struct MockLcu {
+    state: Arc<Mutex<MockState>>,
+    lockfile: PathBuf,
+    port: u16,
+    server: JoinHandle<()>
+}
+
+impl MockLcu {
+
+    /// Listens on `port`, or any free port if it is `0`, and writes the lockfile into `client_dir`.
+    pub async fn start(client_dir: &Path, port: u16) -> Result<Self> {
+        let acceptor = TlsAcceptor::new(IDENTITY, IDENTITY_PASSWORD).await?;
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        let port = listener.local_addr()?.port();
+
+        fs::create_dir_all(client_dir.join("Config")).await?;
+        fs::write(client_dir.join("LeagueClient.exe"), "").await?;
+        let lockfile = client_dir.join("lockfile");
+        fs::write(&lockfile, format!("LeagueClient:{}:{}:{}:https", std::process::id(), port, "mock-password")).await?;
+        log::info!("Mock client listening on port {}", port);
+
+        let state = Arc::new(Mutex::new(MockState::new()));
+        let server = task::spawn({
+            let state = state.clone();
+            async move {
+                let mut incoming = listener.incoming();
+                while let Some(stream) = incoming.next().await {
+                    let (state, acceptor) = (state.clone(), acceptor.clone());
+                    task::spawn(async move {
+                        let result = match stream {
+                            Ok(stream) => match acceptor.accept(stream).await {
+                                Ok(stream) => handle_connection(state, stream).await,
+                                Err(err) => Err(err.into())
+                            },
+                            Err(err) => Err(err.into())
+                        };
+                        if let Err(err) = result {
+                            log::warn!("Connection failed: {}", err);
+                        }
+                    });
+                }
+            }
+        });
+
+        Ok(Self {
+            state,
+            lockfile,
+            port,
+            server
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Replaces the resource at `uri` and pushes an `Update` event.
+    pub async fn set(&self, uri: &str, value: Value) {
+        self.state.lock().await.update(uri, "Update", value).await
+    }
+
+    /// Creates the resource at `uri` and pushes a `Create` event.
+    pub async fn create(&self, uri: &str, value: Value) {
+        self.state.lock().await.update(uri, "Create", value).await
+    }
+
+    /// Removes the resource at `uri` and pushes a `Delete` event.
+    pub async fn delete(&self, uri: &str) {
+        self.state.lock().await.update(uri, "Delete", Value::Null).await
+    }
+
+    pub async fn set_phase(&self, phase: &str) {
+        self.set("/lol-gameflow/v1/gameflow-phase", json!(phase)).await
+    }
+
+    /// Starts a ready check that can be accepted through the api.
+    pub async fn start_ready_check(&self) {
+        self.set(READY_CHECK, json!({"state": "InProgress", "playerResponse": "None", "timer": 0.0})).await
+    }
+
+    /// Answers the ready check like the player would, e.g. with `Declined`.
+    pub async fn respond_to_ready_check(&self, response: &str) {
+        self.state.lock().await.respond_to_ready_check(response).await
+    }
+
+    /// Every request received so far.
+    pub async fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().await.requests.clone()
+    }
+
+    /// Checks if a websocket client subscribed to the events of `uri`.
+    pub async fn is_subscribed(&self, uri: &str) -> bool {
+        let name = format!("OnJsonApiEvent{}", uri).replace('/', "_");
+        for client in &self.state.lock().await.clients {
+            if client.subscriptions.lock().await.contains(&name) {
+                return true
+            }
+        }
+        false
+    }
+
+    /// Shuts the server down and removes the lockfile, like a closing client.
+    pub async fn stop(self) -> Result<()> {
+        self.server.cancel().await;
+        fs::remove_file(&self.lockfile).await?;
+        Ok(())
+    }
+
+}